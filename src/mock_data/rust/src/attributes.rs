@@ -0,0 +1,277 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::{Duration, Months, NaiveDate};
+use fake::Rng;
+use rand_distr::{Distribution, Normal};
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::locations::AliasTable;
+
+/// Youngest and oldest possible ages of a person
+pub const MIN_AGE: u8 = 18;
+pub const MAX_AGE: u8 = 100;
+
+fn parse_age(s: &str) -> Result<u8, String> {
+    let age: u8 = s
+        .trim()
+        .parse()
+        .map_err(|err| format!("Invalid age {s:?}: {err}"))?;
+    if !(MIN_AGE..=MAX_AGE).contains(&age) {
+        return Err(format!("Age {age} must be between {MIN_AGE} and {MAX_AGE}"));
+    }
+    Ok(age)
+}
+
+/// An age distribution as given on the command line: `uniform:MIN-MAX`, `normal:MEAN,STD_DEV` or
+/// `histogram:FILE`
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgeSpec {
+    Uniform { min: u8, max: u8 },
+    Normal { mean: f64, std_dev: f64 },
+    Histogram(PathBuf),
+}
+
+impl FromStr for AgeSpec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        AgeSpec::parse(s).map_err(Error::InvalidAges)
+    }
+}
+
+impl AgeSpec {
+    fn parse(s: &str) -> Result<Self, String> {
+        let (kind, params) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Age distribution {s:?} must be of the form KIND:PARAMS"))?;
+        match kind {
+            "uniform" => {
+                let (min, max) = params.split_once('-').ok_or(
+                    "A uniform age distribution must be of the form uniform:MIN-MAX".to_string(),
+                )?;
+                let (min, max) = (parse_age(min)?, parse_age(max)?);
+                if min > max {
+                    return Err(format!(
+                        "The min age {min} is greater than the max age {max}"
+                    ));
+                }
+                Ok(AgeSpec::Uniform { min, max })
+            }
+            "normal" => {
+                let (mean, std_dev) = params.split_once(',').ok_or(
+                    "A normal age distribution must be of the form normal:MEAN,STD_DEV".to_string(),
+                )?;
+                let mean: f64 = mean
+                    .trim()
+                    .parse()
+                    .map_err(|err| format!("Invalid mean age: {err}"))?;
+                let std_dev: f64 = std_dev
+                    .trim()
+                    .parse()
+                    .map_err(|err| format!("Invalid age std dev: {err}"))?;
+                if !(MIN_AGE as f64..=MAX_AGE as f64).contains(&mean) {
+                    return Err(format!(
+                        "The mean age must be between {MIN_AGE} and {MAX_AGE}"
+                    ));
+                }
+                if !std_dev.is_finite() || std_dev <= 0.0 {
+                    return Err("The age std dev must be positive".to_string());
+                }
+                Ok(AgeSpec::Normal { mean, std_dev })
+            }
+            "histogram" => Ok(AgeSpec::Histogram(PathBuf::from(params))),
+            _ => Err(format!(
+                "Unknown age distribution {kind:?}, expected uniform, normal or histogram"
+            )),
+        }
+    }
+}
+
+/// A row of an age histogram file
+#[derive(Debug, Deserialize)]
+struct AgeBand {
+    min_age: u8,
+    max_age: u8,
+    weight: f64,
+}
+
+/// The distribution the ages of persons are sampled from
+#[derive(Debug, Clone)]
+pub enum AgeDistribution {
+    Uniform {
+        min: u8,
+        max: u8,
+    },
+    /// Rounded to the nearest age and clamped between `MIN_AGE` and `MAX_AGE`
+    Normal(Normal<f64>),
+    /// Age bands sampled by weight, with a uniform age within each band
+    Histogram {
+        bands: Vec<(u8, u8)>,
+        table: AliasTable,
+    },
+}
+
+impl AgeDistribution {
+    pub fn new(spec: &AgeSpec) -> Result<Self, Error> {
+        match spec {
+            AgeSpec::Uniform { min, max } => Ok(AgeDistribution::Uniform {
+                min: *min,
+                max: *max,
+            }),
+            AgeSpec::Normal { mean, std_dev } => Normal::new(*mean, *std_dev)
+                .map(AgeDistribution::Normal)
+                .map_err(|err| Error::InvalidAges(err.to_string())),
+            AgeSpec::Histogram(path) => {
+                Self::read_histogram(path).map_err(|message| Error::AgeHistogram {
+                    path: path.to_path_buf(),
+                    message,
+                })
+            }
+        }
+    }
+
+    /// Read a CSV file of age bands, with `min_age`, `max_age` and `weight` columns
+    fn read_histogram(path: &Path) -> Result<Self, String> {
+        let mut rdr = csv::Reader::from_path(path).map_err(|err| err.to_string())?;
+        let mut bands = Vec::new();
+        let mut weights = Vec::new();
+        for band in rdr.deserialize() {
+            let AgeBand {
+                min_age,
+                max_age,
+                weight,
+            } = band.map_err(|err| err.to_string())?;
+            if min_age < MIN_AGE || max_age > MAX_AGE || min_age > max_age {
+                return Err(format!(
+                    "Age band {min_age}-{max_age} must be within {MIN_AGE}-{MAX_AGE}"
+                ));
+            }
+            bands.push((min_age, max_age));
+            weights.push(weight);
+        }
+        let table = AliasTable::new(&weights).map_err(|err| err.to_string())?;
+        Ok(AgeDistribution::Histogram { bands, table })
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> u8 {
+        match self {
+            AgeDistribution::Uniform { min, max } => rng.gen_range(*min..=*max),
+            AgeDistribution::Normal(normal) => normal
+                .sample(rng)
+                .round()
+                .clamp(MIN_AGE as f64, MAX_AGE as f64)
+                as u8,
+            AgeDistribution::Histogram { bands, table } => {
+                let (min, max) = bands[table.sample(rng)];
+                rng.gen_range(min..=max)
+            }
+        }
+    }
+}
+
+/// The probability of being married by age band, as comma-separated `MIN_AGE:PROBABILITY` pairs.
+/// Each band runs from its min age up to the min age of the next band.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarriageRates {
+    // Sorted by min age
+    bands: Vec<(u8, f64)>,
+}
+
+impl FromStr for MarriageRates {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut bands = s
+            .split(',')
+            .map(|band| {
+                let (age, probability) = band.split_once(':').ok_or_else(|| {
+                    format!("Marriage rate {band:?} must be of the form MIN_AGE:PROBABILITY")
+                })?;
+                let probability: f64 = probability.trim().parse().map_err(|err| {
+                    format!("Invalid marriage probability {probability:?}: {err}")
+                })?;
+                if !(0.0..=1.0).contains(&probability) {
+                    return Err(format!(
+                        "Marriage probability {probability} must be between 0 and 1"
+                    ));
+                }
+                Ok((parse_age(age)?, probability))
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(Error::InvalidMarriageRates)?;
+        bands.sort_by_key(|band| band.0);
+        Ok(MarriageRates { bands })
+    }
+}
+
+impl MarriageRates {
+    /// The probability of being married at `age`, which is 0 below the first band
+    pub fn probability(&self, age: u8) -> f64 {
+        self.bands
+            .iter()
+            .rev()
+            .find(|band| band.0 <= age)
+            .map_or(0.0, |band| band.1)
+    }
+}
+
+/// A date of birth such that a person born on it is `age` years old on `as_of`
+pub fn date_of_birth<R: Rng + ?Sized>(rng: &mut R, age: u8, as_of: NaiveDate) -> NaiveDate {
+    let years_before = |years: u32| {
+        as_of
+            .checked_sub_months(Months::new(12 * years))
+            .unwrap_or(NaiveDate::MIN)
+    };
+    // Born after the day `age + 1` years before, and at the latest `age` years before
+    let latest = years_before(age as u32);
+    let earliest = years_before(age as u32 + 1) + Duration::days(1);
+    let days = (latest - earliest).num_days();
+    earliest + Duration::days(rng.gen_range(0..=days))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_age_distributions() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let uniform = AgeDistribution::new(&"uniform:30-40".parse().unwrap()).unwrap();
+        assert!((0..1000).all(|_| (30..=40).contains(&uniform.sample(&mut rng))));
+        let normal = AgeDistribution::new(&"normal:40,10".parse().unwrap()).unwrap();
+        let ages: Vec<u8> = (0..10_000).map(|_| normal.sample(&mut rng)).collect();
+        let mean = ages.iter().map(|&a| a as f64).sum::<f64>() / ages.len() as f64;
+        assert!((mean - 40.0).abs() < 0.5);
+        assert!(ages.iter().all(|a| (MIN_AGE..=MAX_AGE).contains(a)));
+        let histogram =
+            AgeDistribution::new(&"histogram:../distributions/ages.csv".parse().unwrap()).unwrap();
+        assert!((0..1000).all(|_| (MIN_AGE..=MAX_AGE).contains(&histogram.sample(&mut rng))));
+        for spec in ["uniform:40-30", "uniform:10-30", "normal:40,0", "gamma:1,2"] {
+            assert!(spec.parse::<AgeSpec>().is_err(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn test_marriage_rates_and_date_of_birth() {
+        let rates: MarriageRates = "30:0.6,18:0.1,50:0.7".parse().unwrap();
+        assert_eq!(rates.probability(18), 0.1);
+        assert_eq!(rates.probability(29), 0.1);
+        assert_eq!(rates.probability(30), 0.6);
+        assert_eq!(rates.probability(99), 0.7);
+        assert!("18:1.5".parse::<MarriageRates>().is_err());
+        let mut rng = StdRng::seed_from_u64(1);
+        let as_of = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        for age in [18, 42, 100] {
+            for _ in 0..100 {
+                let dob = date_of_birth(&mut rng, age, as_of);
+                // The person turned `age` at the latest on `as_of`, but not yet `age + 1`
+                let birthday = |years| dob.checked_add_months(Months::new(12 * years)).unwrap();
+                assert!(birthday(age as u32) <= as_of, "{dob}");
+                assert!(birthday(age as u32 + 1) > as_of, "{dob}");
+            }
+        }
+    }
+}
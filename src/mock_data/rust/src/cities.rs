@@ -0,0 +1,364 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+
+use csv::StringRecord;
+
+use crate::error::Error as MockDataError;
+use crate::{convert_unicode_to_ascii, Location};
+
+/// Columns of the GeoNames dumps (e.g. `cities15000.txt`), which have no header row
+const GEONAMES_COLUMNS: [&str; 19] = [
+    "geonameid",
+    "name",
+    "asciiname",
+    "alternatenames",
+    "latitude",
+    "longitude",
+    "feature_class",
+    "feature_code",
+    "country_code",
+    "cc2",
+    "admin1_code",
+    "admin2_code",
+    "admin3_code",
+    "admin4_code",
+    "population",
+    "elevation",
+    "dem",
+    "timezone",
+    "modification_date",
+];
+
+/// Layout of the cities file
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum CitiesFormat {
+    // CSV file with a header row, like the world cities dataset
+    Worldcities,
+    // Tab-separated GeoNames dump without a header row, like cities15000.txt
+    Geonames,
+}
+
+/// The columns of a cities file that each location field is read from. Optional columns can be
+/// missing from the file: the state is then empty, the country is its ISO code, and all cities get
+/// the same population.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CityColumns {
+    pub city: String,
+    pub state: Option<String>,
+    pub country: Option<String>,
+    pub country_code: String,
+    pub lat: String,
+    pub lng: String,
+    pub population: Option<String>,
+}
+
+impl CityColumns {
+    /// The default columns of a cities file format
+    pub fn new(format: CitiesFormat) -> Self {
+        let column = |name: &str| name.to_string();
+        match format {
+            CitiesFormat::Worldcities => CityColumns {
+                city: column("city_ascii"),
+                state: Some(column("admin_name")),
+                country: Some(column("country")),
+                country_code: column("iso2"),
+                lat: column("lat"),
+                lng: column("lng"),
+                population: Some(column("population")),
+            },
+            // GeoNames dumps only have the code of the state, and no country name
+            CitiesFormat::Geonames => CityColumns {
+                city: column("asciiname"),
+                state: Some(column("admin1_code")),
+                country: None,
+                country_code: column("country_code"),
+                lat: column("latitude"),
+                lng: column("longitude"),
+                population: Some(column("population")),
+            },
+        }
+    }
+
+    /// Override the columns of some fields
+    pub fn with_mapping(mut self, mapping: &ColumnMapping) -> Result<Self, MockDataError> {
+        for (field, column) in mapping.0.iter() {
+            let optional = (!column.is_empty()).then(|| column.clone());
+            let required = || {
+                optional.clone().ok_or_else(|| {
+                    MockDataError::InvalidColumnMapping(format!("The {field} column is required"))
+                })
+            };
+            match field.as_str() {
+                "city" => self.city = required()?,
+                "state" => self.state = optional,
+                "country" => self.country = optional,
+                "country_code" => self.country_code = required()?,
+                "lat" => self.lat = required()?,
+                "lng" => self.lng = required()?,
+                "population" => self.population = optional,
+                _ => {
+                    return Err(MockDataError::InvalidColumnMapping(format!(
+                        "Unknown city field {field:?}, expected city, state, country, \
+                         country_code, lat, lng or population"
+                    )))
+                }
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// Overrides of the columns of some location fields, as comma-separated `FIELD=COLUMN` pairs. An
+/// empty column marks an optional field as missing from the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMapping(Vec<(String, String)>);
+
+impl FromStr for ColumnMapping {
+    type Err = MockDataError;
+
+    fn from_str(s: &str) -> Result<Self, MockDataError> {
+        s.split(',')
+            .map(|pair| {
+                let (field, column) = pair.split_once('=').ok_or_else(|| {
+                    MockDataError::InvalidColumnMapping(format!(
+                        "Column mapping {pair:?} must be of the form FIELD=COLUMN"
+                    ))
+                })?;
+                Ok((field.trim().to_string(), column.trim().to_string()))
+            })
+            .collect::<Result<_, _>>()
+            .map(ColumnMapping)
+    }
+}
+
+/// The locations read from a cities file, and the number of rows that were skipped
+#[derive(Debug)]
+pub struct Cities {
+    pub locations: Vec<Location>,
+    // Rows with a missing city or country code, or invalid coordinates
+    pub num_invalid: usize,
+    // Rows with the same city, state, country code and coordinates as a previous row
+    pub num_duplicates: usize,
+}
+
+/// Indices of the columns of `CityColumns` in the rows of a cities file
+struct ColumnIndices {
+    city: usize,
+    state: Option<usize>,
+    country: Option<usize>,
+    country_code: usize,
+    lat: usize,
+    lng: usize,
+    population: Option<usize>,
+}
+
+impl ColumnIndices {
+    fn new(
+        path: &Path,
+        headers: &StringRecord,
+        columns: &CityColumns,
+    ) -> Result<Self, MockDataError> {
+        let find = |field: &str, column: &str| match headers
+            .iter()
+            .position(|header| header.trim() == column)
+        {
+            Some(index) => Ok(index),
+            None => Err(MockDataError::CitiesParse {
+                path: path.to_path_buf(),
+                row: None,
+                message: format!(
+                    "Column {column:?} of the {field} not found. Available columns: {}",
+                    headers.iter().collect::<Vec<_>>().join(", ")
+                ),
+            }),
+        };
+        let find_optional = |field: &str, column: &Option<String>| {
+            column
+                .as_ref()
+                .map(|column| find(field, column))
+                .transpose()
+        };
+        Ok(ColumnIndices {
+            city: find("city", &columns.city)?,
+            state: find_optional("state", &columns.state)?,
+            country: find_optional("country", &columns.country)?,
+            country_code: find("country_code", &columns.country_code)?,
+            lat: find("lat", &columns.lat)?,
+            lng: find("lng", &columns.lng)?,
+            population: find_optional("population", &columns.population)?,
+        })
+    }
+
+    /// The location of a row, or `None` if the row is invalid
+    fn location(&self, record: &StringRecord) -> Option<Location> {
+        let field = |index: usize| record.get(index).map(str::trim);
+        let optional = |index: Option<usize>| index.and_then(field).unwrap_or_default();
+        let city = field(self.city).filter(|city| !city.is_empty())?;
+        let country_code = field(self.country_code).filter(|code| !code.is_empty())?;
+        let lat: f64 = field(self.lat)?.parse().ok()?;
+        let lng: f64 = field(self.lng)?.parse().ok()?;
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lng) {
+            return None;
+        }
+        let country = match optional(self.country) {
+            "" => country_code,
+            country => country,
+        };
+        Some(Location {
+            city: city.to_string(),
+            state: convert_unicode_to_ascii(optional(self.state)),
+            country: country.to_string(),
+            country_code: country_code.to_string(),
+            lat,
+            lng,
+            population: optional(self.population).parse().ok(),
+        })
+    }
+}
+
+/// The error of a cities file for a CSV error: I/O errors mean that the file can't be read, and
+/// other errors that its contents are invalid
+fn cities_error(path: &Path, err: csv::Error) -> MockDataError {
+    let path = path.to_path_buf();
+    if err.is_io_error() {
+        MockDataError::CitiesFile { path, source: err }
+    } else {
+        MockDataError::CitiesParse {
+            path,
+            row: err.position().map(|position| position.line()),
+            message: err.to_string(),
+        }
+    }
+}
+
+/// Read the locations of a cities file, looking up the columns of `columns` by name. Invalid and
+/// duplicate rows are skipped.
+pub fn read_cities(
+    path: &Path,
+    format: CitiesFormat,
+    columns: &CityColumns,
+) -> Result<Cities, MockDataError> {
+    let mut builder = csv::ReaderBuilder::new();
+    builder.flexible(true);
+    if format == CitiesFormat::Geonames {
+        builder.delimiter(b'\t').has_headers(false).quoting(false);
+    }
+    let mut rdr = builder
+        .from_path(path)
+        .map_err(|err| cities_error(path, err))?;
+    let headers = match format {
+        CitiesFormat::Worldcities => rdr
+            .headers()
+            .map_err(|err| cities_error(path, err))?
+            .clone(),
+        CitiesFormat::Geonames => StringRecord::from(GEONAMES_COLUMNS.to_vec()),
+    };
+    let indices = ColumnIndices::new(path, &headers, columns)?;
+    let mut cities = Cities {
+        locations: Vec::new(),
+        num_invalid: 0,
+        num_duplicates: 0,
+    };
+    let mut seen = HashSet::new();
+    for record in rdr.records() {
+        let location = match record {
+            Ok(record) => indices.location(&record),
+            Err(err) if err.is_io_error() => return Err(cities_error(path, err)),
+            Err(_) => None,
+        };
+        let Some(location) = location else {
+            cities.num_invalid += 1;
+            continue;
+        };
+        let key = (
+            location.city.clone(),
+            location.state.clone(),
+            location.country_code.clone(),
+            location.lat.to_bits(),
+            location.lng.to_bits(),
+        );
+        if seen.insert(key) {
+            cities.locations.push(location);
+        } else {
+            cities.num_duplicates += 1;
+        }
+    }
+    Ok(cities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("mock_data_{}_{name}", std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_read_cities_by_header() {
+        // Columns in a different order than worldcities.csv, with invalid and duplicate rows
+        let path = write(
+            "cities.csv",
+            "iso2,city_ascii,lng,lat,admin_name,country,population\n\
+             JP,Tokyo,139.6922,35.6897,Tōkyō,Japan,37732000\n\
+             BR,Recife,-34.8808,-8.0539,Pernambuco,Brazil,\n\
+             BR,Nowhere,not a number,-8.0,Pernambuco,Brazil,1\n\
+             ,Nocountry,0.0,0.0,,,1\n\
+             FR,Short\n\
+             JP,Tokyo,139.6922,35.6897,Tōkyō,Japan,37732000\n",
+        );
+        let columns = CityColumns::new(CitiesFormat::Worldcities);
+        let cities = read_cities(&path, CitiesFormat::Worldcities, &columns).unwrap();
+        assert_eq!(cities.locations.len(), 2);
+        assert_eq!((cities.num_invalid, cities.num_duplicates), (3, 1));
+        let tokyo = &cities.locations[0];
+        assert_eq!((tokyo.state.as_str(), tokyo.lat), ("Tokyo", 35.6897));
+        assert_eq!(cities.locations[1].population, None);
+
+        // A missing column is named in the error
+        let columns = columns
+            .with_mapping(&"state=province".parse().unwrap())
+            .unwrap();
+        let err = read_cities(&path, CitiesFormat::Worldcities, &columns).unwrap_err();
+        assert!(matches!(err, MockDataError::CitiesParse { row: None, .. }));
+        assert!(err
+            .to_string()
+            .contains("Column \"province\" of the state not found"));
+        fs::remove_file(&path).unwrap();
+
+        let err = read_cities(&path, CitiesFormat::Worldcities, &columns).unwrap_err();
+        assert!(matches!(err, MockDataError::CitiesFile { .. }));
+    }
+
+    #[test]
+    fn test_read_geonames_cities() {
+        let path = write(
+            "cities15000.txt",
+            "1850147\tTokyo\tTokyo\tTokio,東京\t35.6895\t139.69171\tP\tPPLC\tJP\t\t40\t\t\t\t\
+             8336599\t\t44\tAsia/Tokyo\t2024-01-01\n",
+        );
+        let columns = CityColumns::new(CitiesFormat::Geonames);
+        let cities = read_cities(&path, CitiesFormat::Geonames, &columns).unwrap();
+        let tokyo = &cities.locations[0];
+        assert_eq!(tokyo.city, "Tokyo");
+        assert_eq!((tokyo.state.as_str(), tokyo.country.as_str()), ("40", "JP"));
+        assert_eq!(tokyo.population, Some(8336599.0));
+        fs::remove_file(path).unwrap();
+
+        let mapping: ColumnMapping = "city=name, population=".parse().unwrap();
+        let columns = CityColumns::new(CitiesFormat::Geonames)
+            .with_mapping(&mapping)
+            .unwrap();
+        assert_eq!(
+            (columns.city.as_str(), columns.population.as_deref()),
+            ("name", None)
+        );
+        assert!(columns.with_mapping(&"lat=".parse().unwrap()).is_err());
+        assert!("city".parse::<ColumnMapping>().is_err());
+    }
+}
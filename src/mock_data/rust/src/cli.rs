@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+use clap::Parser;
+use mock_data::attributes::{AgeSpec, MarriageRates};
+use mock_data::cities::{CitiesFormat, ColumnMapping};
+use mock_data::defects::DefectKind;
+use mock_data::output::Format;
+use mock_data::ExtraField;
+
+#[derive(Debug, Parser)]
+#[clap(version, about = "Generate reproducible mock person profiles")]
+pub struct Args {
+    #[clap(
+        short = 'n',
+        long,
+        default_value_t = 10,
+        help = "Number of person profiles to generate"
+    )]
+    pub count: u32,
+
+    #[clap(
+        short,
+        long,
+        default_value_t = 0,
+        help = "Base random seed. The same seed and count always produce the same profiles"
+    )]
+    pub seed: u64,
+
+    #[clap(
+        short,
+        long,
+        default_value = "../data/persons.csv",
+        help = "Output file path"
+    )]
+    pub output: PathBuf,
+
+    #[clap(short, long, value_enum, default_value_t = Format::Csv, help = "Output file format")]
+    pub format: Format,
+
+    #[clap(
+        short,
+        long,
+        default_value = "../data/worldcities.csv",
+        help = "CSV file of world cities to sample locations from"
+    )]
+    pub cities: PathBuf,
+
+    #[clap(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        default_value_t = CitiesFormat::Worldcities,
+        help = "Format of the cities file"
+    )]
+    pub cities_format: CitiesFormat,
+
+    #[clap(
+        long,
+        value_name = "MAPPING",
+        help = "Comma-separated FIELD=COLUMN overrides of the cities file columns of the city, state, country, country_code, lat, lng and population fields"
+    )]
+    pub city_columns: Option<ColumnMapping>,
+
+    #[clap(
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated names or ISO codes of the countries to sample locations from. Default: all countries"
+    )]
+    pub countries: Vec<String>,
+
+    #[clap(
+        short,
+        long,
+        default_value = "uniform:18-64",
+        help = "Age distribution: uniform:MIN-MAX, normal:MEAN,STD_DEV or histogram:FILE (a CSV file of min_age, max_age and weight columns)"
+    )]
+    pub ages: AgeSpec,
+
+    #[clap(
+        short,
+        long,
+        value_name = "RATES",
+        default_value = "18:0.5",
+        help = "Probability of being married by age band, as comma-separated MIN_AGE:PROBABILITY pairs"
+    )]
+    pub marriage_rates: MarriageRates,
+
+    #[clap(
+        short,
+        long,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "FIELDS",
+        help = "Comma-separated optional fields to add to the profiles"
+    )]
+    pub extra_fields: Vec<ExtraField>,
+
+    #[clap(
+        long,
+        help = "Date on which the dates of birth match the ages, as YYYY-MM-DD. Default: today"
+    )]
+    pub as_of: Option<NaiveDate>,
+
+    #[clap(
+        short,
+        long,
+        help = "Generate names in the locale of the person's country, where supported"
+    )]
+    pub local_names: bool,
+
+    #[clap(
+        long,
+        help = "Convert names to ASCII. Names in non-Latin scripts are replaced by English names"
+    )]
+    pub ascii_names: bool,
+
+    #[clap(
+        long,
+        value_name = "RATE",
+        conflicts_with_all = ["schema", "households"],
+        help = "Share of the person profiles with a data-quality defect (greater than 0 and at most 1), listed in a .defects.jsonl manifest next to the output file"
+    )]
+    pub defect_rate: Option<f64>,
+
+    #[clap(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "KINDS",
+        conflicts_with_all = ["schema", "households"],
+        requires = "defect_rate",
+        help = "Comma-separated kinds of defects to inject. Default: all those that apply to the fields of the profiles"
+    )]
+    pub defects: Vec<DefectKind>,
+
+    #[clap(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["output", "extra_fields"],
+        help = "Generate COUNT households of adults and children instead, written as households, persons and children files in DIR"
+    )]
+    pub households: Option<PathBuf>,
+
+    #[clap(
+        long,
+        help = "TOML or JSON schema file of the records to generate, instead of person profiles"
+    )]
+    pub schema: Option<PathBuf>,
+
+    #[clap(
+        short,
+        long,
+        help = "Number of worker threads. Default: number of CPU cores"
+    )]
+    pub threads: Option<usize>,
+
+    #[clap(
+        long,
+        default_value_t = 100_000,
+        help = "Number of profiles generated in parallel and written per chunk"
+    )]
+    pub chunk_size: u32,
+}
@@ -0,0 +1,398 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use fake::Rng;
+use rand::seq::SliceRandom;
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::person_seed;
+use crate::table::{Column, ColumnType, Row, Value};
+
+/// Mixed into the seed of the defects of a row, so that they are independent of its fields
+const DEFECT_SEED_SALT: u64 = 0x5EED_DEFE_C75E_ED00;
+
+/// Kinds of data-quality defects that can be injected into person profiles
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum DefectKind {
+    MissingField,
+    InvalidAge,
+    MalformedDate,
+    DuplicateId,
+    UnicodeOddity,
+    ExtraWhitespace,
+}
+
+impl DefectKind {
+    pub const ALL: [DefectKind; 6] = [
+        DefectKind::MissingField,
+        DefectKind::InvalidAge,
+        DefectKind::MalformedDate,
+        DefectKind::DuplicateId,
+        DefectKind::UnicodeOddity,
+        DefectKind::ExtraWhitespace,
+    ];
+
+    /// The name of the kind, as in the manifest
+    pub fn name(&self) -> &'static str {
+        match self {
+            DefectKind::MissingField => "missing-field",
+            DefectKind::InvalidAge => "invalid-age",
+            DefectKind::MalformedDate => "malformed-date",
+            DefectKind::DuplicateId => "duplicate-id",
+            DefectKind::UnicodeOddity => "unicode-oddity",
+            DefectKind::ExtraWhitespace => "extra-whitespace",
+        }
+    }
+
+    /// Whether the kind of defect can be injected into a column, whatever the values of the rows
+    fn applies_to(&self, column: &Column) -> bool {
+        match self {
+            DefectKind::MissingField => column.name != "id",
+            DefectKind::InvalidAge => column.name == "age",
+            DefectKind::MalformedDate => column.name == "dateOfBirth",
+            DefectKind::DuplicateId => column.name == "id",
+            DefectKind::UnicodeOddity | DefectKind::ExtraWhitespace => {
+                column.column_type == ColumnType::Str
+            }
+        }
+    }
+}
+
+/// A defect injected into a row, as listed in the manifest
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Defect {
+    // Id of the row before any defect, which is also its position in the output
+    pub id: u32,
+    pub kind: DefectKind,
+    pub column: String,
+    pub original: Value,
+    pub corrupted: Value,
+}
+
+/// A row, and the defect injected into it if any
+pub struct DefectiveRow {
+    pub row: Row,
+    pub defect: Option<Defect>,
+}
+
+impl AsRef<[Value]> for DefectiveRow {
+    fn as_ref(&self) -> &[Value] {
+        &self.row
+    }
+}
+
+/// The kinds of defects that can be injected into rows with the given columns
+pub fn applicable_kinds(columns: &[Column]) -> Vec<DefectKind> {
+    DefectKind::ALL
+        .into_iter()
+        .filter(|kind| columns.iter().any(|column| kind.applies_to(column)))
+        .collect()
+}
+
+/// Injects defects of the given kinds into a share of the rows. Each defective row has a single
+/// defect, and like the rows themselves, the defects only depend on the seed and the row id.
+pub struct DefectInjector {
+    rate: f64,
+    kinds: Vec<DefectKind>,
+    columns: Vec<Column>,
+    seed: u64,
+}
+
+impl DefectInjector {
+    pub fn new(
+        rate: f64,
+        kinds: &[DefectKind],
+        columns: Vec<Column>,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        let invalid = |message: String| Err(Error::InvalidDefects(message));
+        if !(rate > 0.0 && rate <= 1.0) {
+            return invalid("The defect rate must be greater than 0 and at most 1".to_string());
+        }
+        if kinds.is_empty() {
+            return invalid("At least one kind of defect must be given".to_string());
+        }
+        if let Some(kind) = kinds
+            .iter()
+            .find(|kind| !columns.iter().any(|column| kind.applies_to(column)))
+        {
+            return invalid(format!(
+                "Defects of kind {} can't be injected, as no column can have them",
+                kind.name()
+            ));
+        }
+        Ok(DefectInjector {
+            rate,
+            kinds: kinds.to_vec(),
+            columns,
+            seed,
+        })
+    }
+
+    /// The columns of the defective rows, which are nullable if fields can be missing, and with
+    /// signed ages if they can be invalid
+    pub fn columns(&self) -> Vec<Column> {
+        let nullable = self.kinds.contains(&DefectKind::MissingField);
+        let invalid_ages = self.kinds.contains(&DefectKind::InvalidAge);
+        self.columns
+            .iter()
+            .map(|column| Column {
+                nullable: column.nullable || nullable,
+                column_type: if invalid_ages && column.name == "age" {
+                    ColumnType::Int
+                } else {
+                    column.column_type
+                },
+                ..column.clone()
+            })
+            .collect()
+    }
+
+    /// The columns of `row` that a kind of defect can be injected into
+    fn targets(&self, kind: DefectKind, id: u32, row: &Row) -> Vec<usize> {
+        let is_date = |value: &Value| match value {
+            Value::Str(s) => NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok(),
+            _ => false,
+        };
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(|(index, column)| {
+                let value = &row[*index];
+                kind.applies_to(column)
+                    && match kind {
+                        DefectKind::MissingField => *value != Value::Null,
+                        DefectKind::InvalidAge => true,
+                        DefectKind::MalformedDate => is_date(value),
+                        // The first row has no previous id to duplicate
+                        DefectKind::DuplicateId => id > 1,
+                        DefectKind::UnicodeOddity | DefectKind::ExtraWhitespace => {
+                            matches!(value, Value::Str(s) if !s.is_empty() && !is_date(value))
+                        }
+                    }
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Inject a defect into the row with the given id, with probability `rate`
+    pub fn inject(&self, id: u32, mut row: Row) -> DefectiveRow {
+        let mut r = StdRng::seed_from_u64(person_seed(self.seed, id) ^ DEFECT_SEED_SALT);
+        if !r.gen_bool(self.rate) {
+            return DefectiveRow { row, defect: None };
+        }
+        let candidates: Vec<(DefectKind, Vec<usize>)> = self
+            .kinds
+            .iter()
+            .map(|&kind| (kind, self.targets(kind, id, &row)))
+            .filter(|(_, targets)| !targets.is_empty())
+            .collect();
+        let Some((kind, targets)) = candidates.choose(&mut r) else {
+            return DefectiveRow { row, defect: None };
+        };
+        let index = *targets.choose(&mut r).unwrap();
+        let original = row[index].clone();
+        let corrupted = corrupt(*kind, &original, id, &mut r);
+        row[index] = corrupted.clone();
+        let defect = Defect {
+            id,
+            kind: *kind,
+            column: self.columns[index].name.clone(),
+            original,
+            corrupted,
+        };
+        DefectiveRow {
+            row,
+            defect: Some(defect),
+        }
+    }
+}
+
+/// The value of a field with a defect of the given kind
+fn corrupt<R: Rng>(kind: DefectKind, value: &Value, id: u32, r: &mut R) -> Value {
+    let text = value.to_field();
+    match kind {
+        DefectKind::MissingField => Value::Null,
+        DefectKind::InvalidAge => {
+            if r.gen_bool(0.5) {
+                Value::Int(-r.gen_range(1..=10))
+            } else {
+                Value::Int(r.gen_range(150..=999))
+            }
+        }
+        DefectKind::MalformedDate => {
+            let date = NaiveDate::parse_from_str(&text, "%Y-%m-%d").unwrap_or_default();
+            let formats = ["%d/%m/%Y", "%Y/%m/%d", "%m-%d-%Y", "%Y-%m-%dT"];
+            match r.gen_range(0..=formats.len()) {
+                // A date that doesn't exist
+                0 => Value::Str(date.format("%Y-02-30").to_string()),
+                i => Value::Str(date.format(formats[i - 1]).to_string()),
+            }
+        }
+        DefectKind::DuplicateId => Value::Int(r.gen_range(1..id) as i64),
+        DefectKind::UnicodeOddity => Value::Str(unicode_oddity(&text, r)),
+        DefectKind::ExtraWhitespace => Value::Str(match r.gen_range(0..4) {
+            0 => format!("  {text}"),
+            1 => format!("{text} "),
+            2 => format!("\t{text}"),
+            _ if text.contains(' ') => text.replacen(' ', "  ", 1),
+            _ => format!(" {text} "),
+        }),
+    }
+}
+
+/// Text that looks the same (or almost) as `text`, but isn't: with a zero-width space, a Cyrillic
+/// look-alike letter, a non-breaking space or a combining accent
+fn unicode_oddity<R: Rng>(text: &str, r: &mut R) -> String {
+    let homoglyphs = [('a', 'а'), ('e', 'е'), ('o', 'о'), ('c', 'с'), ('p', 'р')];
+    let mut oddities: Vec<String> = Vec::new();
+    let middle = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .nth(text.chars().count() / 2)
+        .unwrap_or(0);
+    oddities.push(format!("{}\u{200B}{}", &text[..middle], &text[middle..]));
+    if let Some((latin, cyrillic)) = homoglyphs.iter().find(|(c, _)| text.contains(*c)) {
+        oddities.push(text.replacen(*latin, &cyrillic.to_string(), 1));
+    }
+    if text.contains(' ') {
+        oddities.push(text.replacen(' ', "\u{00A0}", 1));
+    }
+    oddities.push(format!("{text}\u{0301}"));
+    oddities.swap_remove(r.gen_range(0..oddities.len()))
+}
+
+/// The manifest of the defects of an output file: `persons.csv` has `persons.defects.jsonl`
+pub fn manifest_path(output_path: &Path) -> PathBuf {
+    output_path.with_extension("defects.jsonl")
+}
+
+/// Writes the defects of the rows to a JSON lines manifest, one defect per line
+pub struct ManifestWriter {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    num_defects: usize,
+}
+
+impl ManifestWriter {
+    pub fn create(path: &Path) -> Result<Self, Error> {
+        let file = File::create(path).map_err(|err| manifest_error(path, err.into()))?;
+        Ok(ManifestWriter {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            num_defects: 0,
+        })
+    }
+
+    pub fn write(&mut self, rows: &[DefectiveRow]) -> Result<(), Error> {
+        for defect in rows.iter().filter_map(|row| row.defect.as_ref()) {
+            serde_json::to_writer(&mut self.writer, defect)
+                .map_err(|err| manifest_error(&self.path, err.into()))?;
+            writeln!(self.writer).map_err(|err| manifest_error(&self.path, err.into()))?;
+            self.num_defects += 1;
+        }
+        Ok(())
+    }
+
+    /// Flush the manifest, and return the number of defects listed in it
+    pub fn finish(mut self) -> Result<usize, Error> {
+        self.writer
+            .flush()
+            .map_err(|err| manifest_error(&self.path, err.into()))?;
+        Ok(self.num_defects)
+    }
+}
+
+fn manifest_error(path: &Path, source: Box<dyn std::error::Error + Send + Sync>) -> Error {
+    Error::OutputWrite {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::new("id", ColumnType::UInt32),
+            Column::new("name", ColumnType::Str),
+            Column::new("age", ColumnType::UInt8),
+            Column::new("dateOfBirth", ColumnType::Str),
+        ]
+    }
+
+    fn row(id: u32) -> Row {
+        vec![
+            Value::Int(id as i64),
+            Value::Str("Megan Chang".to_string()),
+            Value::Int(48),
+            Value::Str("1976-03-14".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_inject_defects() {
+        let kinds = DefectKind::ALL;
+        assert_eq!(applicable_kinds(&columns()), kinds);
+        let injector = DefectInjector::new(0.2, &kinds, columns(), 42).unwrap();
+        assert!(injector.columns().iter().all(|c| c.nullable));
+        assert_eq!(injector.columns()[2].column_type, ColumnType::Int);
+        let rows: Vec<DefectiveRow> = (1..=1000).map(|id| injector.inject(id, row(id))).collect();
+        let defects: Vec<&Defect> = rows.iter().filter_map(|r| r.defect.as_ref()).collect();
+        assert!((150..250).contains(&defects.len()), "{}", defects.len());
+        for kind in kinds {
+            assert!(defects.iter().any(|d| d.kind == kind), "{kind:?}");
+        }
+        for (defective, id) in rows.iter().zip(1..) {
+            let expected = row(id);
+            match &defective.defect {
+                None => assert_eq!(defective.row, expected),
+                Some(defect) => {
+                    // Exactly the field listed in the manifest is corrupted
+                    let index = columns().iter().position(|c| c.name == defect.column);
+                    let index = index.unwrap();
+                    assert_eq!(defect.original, expected[index]);
+                    assert_eq!(defective.row[index], defect.corrupted);
+                    assert_ne!(defect.corrupted, defect.original);
+                    let differing =
+                        (0..expected.len()).filter(|&i| defective.row[i] != expected[i]);
+                    assert_eq!(differing.count(), 1);
+                    match defect.kind {
+                        DefectKind::InvalidAge => assert!(
+                            matches!(defect.corrupted, Value::Int(age) if !(0..=120).contains(&age))
+                        ),
+                        DefectKind::MalformedDate => assert!(NaiveDate::parse_from_str(
+                            &defect.corrupted.to_field(),
+                            "%Y-%m-%d"
+                        )
+                        .is_err()),
+                        DefectKind::DuplicateId => {
+                            assert!(matches!(defect.corrupted, Value::Int(i) if i < id as i64))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        // The same seed injects the same defects
+        let again = injector.inject(7, row(7));
+        assert_eq!(again.defect, rows[6].defect);
+        assert!(DefectInjector::new(1.5, &kinds, columns(), 42).is_err());
+        assert!(DefectInjector::new(0.0, &kinds, columns(), 42).is_err());
+        // Kinds of defects that no column can have are rejected rather than ignored
+        let without_dates = columns()[..3].to_vec();
+        assert!(!applicable_kinds(&without_dates).contains(&DefectKind::MalformedDate));
+        let err = DefectInjector::new(0.2, &kinds, without_dates, 42)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("malformed-date"), "{err}");
+    }
+}
@@ -0,0 +1,90 @@
+use std::path::{Path, PathBuf};
+
+/// Errors of the public API of the library
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The cities file can't be opened or read
+    #[error("Unable to read cities file {path:?}")]
+    CitiesFile {
+        path: PathBuf,
+        #[source]
+        source: csv::Error,
+    },
+
+    /// The cities file was read, but isn't a valid cities file. `row` is the 1-based line of the
+    /// error in the file, if it is about a specific row.
+    #[error("Invalid cities file {path:?}{}: {message}", at_row(*row))]
+    CitiesParse {
+        path: PathBuf,
+        row: Option<u64>,
+        message: String,
+    },
+
+    /// An output file can't be created or written
+    #[error("Unable to write output file {path:?}")]
+    OutputWrite {
+        path: PathBuf,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// The overrides of the columns of the cities file are invalid
+    #[error("{0}")]
+    InvalidColumnMapping(String),
+
+    /// There are no cities to sample locations from
+    #[error("No cities to sample locations from")]
+    NoCities,
+
+    /// None of the cities are in the countries to sample locations from
+    #[error("No cities found for the countries {countries:?}")]
+    NoMatchingCountries { countries: Vec<String> },
+
+    /// Sampling weights, such as the populations of the cities, are negative or all zero
+    #[error("{0}")]
+    InvalidWeights(String),
+
+    /// The age distribution is invalid
+    #[error("{0}")]
+    InvalidAges(String),
+
+    /// The age histogram file can't be read or is invalid
+    #[error("Invalid age histogram file {path:?}: {message}")]
+    AgeHistogram { path: PathBuf, message: String },
+
+    /// The marriage rates are invalid
+    #[error("{0}")]
+    InvalidMarriageRates(String),
+
+    /// The schema file can't be read
+    #[error("Unable to read schema file {path:?}")]
+    SchemaFile {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// The schema is invalid. `path` is the schema file, if it was loaded from one.
+    #[error("Invalid schema{}: {message}", in_file(path.as_deref()))]
+    InvalidSchema {
+        path: Option<PathBuf>,
+        message: String,
+    },
+
+    /// The defect rate or kinds of defects are invalid
+    #[error("{0}")]
+    InvalidDefects(String),
+
+    /// The persons can't be converted to an Arrow record batch, e.g. if they have too much text
+    #[error("Unable to build an Arrow record batch")]
+    RecordBatch(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+fn at_row(row: Option<u64>) -> String {
+    row.map(|row| format!(" at row {row}")).unwrap_or_default()
+}
+
+fn in_file(path: Option<&Path>) -> String {
+    path.map(|path| format!(" file {path:?}"))
+        .unwrap_or_default()
+}
@@ -0,0 +1,176 @@
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use arrow_array::RecordBatch;
+use rayon::prelude::*;
+
+use crate::cities::{read_cities, CitiesFormat, CityColumns};
+use crate::error::Error;
+use crate::locations::Locations;
+use crate::output::{arrow_schema, to_record_batch, Format, RowWriter};
+use crate::table::{Column, Row};
+use crate::{construct_person, write_rows, Location, Person, PersonOptions};
+
+/// Generates reproducible person profiles living in a set of cities. Each person only depends on
+/// the seed and its id, so persons can be generated in any order, in parallel, or one at a time.
+///
+/// ```no_run
+/// use std::path::Path;
+/// use mock_data::{Generator, PersonOptions};
+///
+/// let generator =
+///     Generator::from_cities_file(Path::new("worldcities.csv"), PersonOptions::default(), 42)?;
+/// let persons = generator.persons(100);
+/// # Ok::<(), mock_data::Error>(())
+/// ```
+pub struct Generator {
+    locations: Locations,
+    options: PersonOptions,
+    seed: u64,
+}
+
+impl Generator {
+    /// A generator of persons living in `cities`, restricted to the cities of `countries` (by name
+    /// or ISO code) unless it is empty
+    pub fn new(
+        cities: Vec<Location>,
+        countries: &[String],
+        options: PersonOptions,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        let locations = Locations::new(cities, countries)?;
+        Ok(Generator {
+            locations,
+            options,
+            seed,
+        })
+    }
+
+    /// A generator of persons living in all the cities of a world cities CSV file
+    pub fn from_cities_file(path: &Path, options: PersonOptions, seed: u64) -> Result<Self, Error> {
+        let columns = CityColumns::new(CitiesFormat::Worldcities);
+        let cities = read_cities(path, CitiesFormat::Worldcities, &columns)?;
+        Generator::new(cities.locations, &[], options, seed)
+    }
+
+    pub fn locations(&self) -> &Locations {
+        &self.locations
+    }
+
+    pub fn options(&self) -> &PersonOptions {
+        &self.options
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The columns of the rows of the persons
+    pub fn columns(&self) -> Vec<Column> {
+        Person::columns(&self.options)
+    }
+
+    /// The person with the given id
+    pub fn person(&self, id: u32) -> Person {
+        construct_person(&self.locations, &self.options, id, self.seed)
+    }
+
+    /// The persons with ids 1 to `count`, constructed in parallel
+    pub fn persons(&self, count: u32) -> Vec<Person> {
+        self.persons_with_ids(1..=count)
+    }
+
+    /// The persons with the given ids, constructed in parallel, e.g. to generate them in batches
+    pub fn persons_with_ids(&self, ids: RangeInclusive<u32>) -> Vec<Person> {
+        ids.into_par_iter().map(|id| self.person(id)).collect()
+    }
+
+    /// The persons with ids 1 to `count` as an Arrow record batch, with the same columns as the
+    /// files written by `write`
+    pub fn record_batch(&self, count: u32) -> Result<RecordBatch, Error> {
+        let columns = self.columns();
+        let rows: Vec<_> = (1..=count)
+            .into_par_iter()
+            .map(|id| self.person(id).into_row())
+            .collect();
+        to_record_batch(arrow_schema(&columns), &columns, &rows)
+    }
+
+    /// Write the persons with ids 1 to `count` to `path`, generating `chunk_size` persons at a time
+    pub fn write(
+        &self,
+        path: &Path,
+        format: Format,
+        count: u32,
+        chunk_size: u32,
+    ) -> Result<(), Error> {
+        write_rows(
+            self.columns(),
+            |id| self.person(id).into_row(),
+            count,
+            chunk_size,
+            path,
+            format,
+        )
+    }
+
+    /// Write already generated persons to `path`, converting `chunk_size` of them to rows at a
+    /// time, e.g. to both write the persons and keep them without generating them twice
+    pub fn write_persons(
+        &self,
+        path: &Path,
+        format: Format,
+        persons: &[Person],
+        chunk_size: u32,
+    ) -> Result<(), Error> {
+        let mut writer = RowWriter::create(path, format, self.columns())?;
+        for chunk in persons.chunks(chunk_size.max(1) as usize) {
+            let rows: Vec<Row> = chunk.iter().map(|p| p.clone().into_row()).collect();
+            writer.write(&rows)?;
+        }
+        writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locations::test_cities;
+    use arrow_schema::DataType;
+    use std::fs;
+
+    #[test]
+    fn test_generator() {
+        let cities = test_cities();
+        let generator = Generator::new(cities.clone(), &[], PersonOptions::default(), 42).unwrap();
+        let persons = generator.persons(20);
+        assert!(persons.iter().map(|p| p.id).eq(1..=20));
+        assert_eq!(persons[4], generator.person(5));
+        assert_eq!(generator.persons_with_ids(5..=9), persons[4..9]);
+
+        let path =
+            std::env::temp_dir().join(format!("mock_data_{}_generator.csv", std::process::id()));
+        generator.write(&path, Format::Csv, 20, 7).unwrap();
+        let mut rdr = csv::Reader::from_path(&path).unwrap();
+        let names: Vec<String> = rdr.records().map(|r| r.unwrap()[1].to_string()).collect();
+        assert!(names.iter().eq(persons.iter().map(|p| &p.name)));
+        let written = fs::read_to_string(&path).unwrap();
+        generator
+            .write_persons(&path, Format::Csv, &persons, 7)
+            .unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), written);
+        fs::remove_file(path).unwrap();
+
+        let batch = generator.record_batch(20).unwrap();
+        assert_eq!((batch.num_rows(), batch.num_columns()), (20, 9));
+        assert_eq!(batch.schema().field(3).name(), "isMarried");
+        assert_eq!(batch.schema().field(0).data_type(), &DataType::UInt32);
+        assert_eq!(batch.schema().field(2).data_type(), &DataType::UInt8);
+
+        let countries = ["Germany".to_string()];
+        let err = Generator::new(cities, &countries, PersonOptions::default(), 42);
+        assert!(matches!(err, Err(Error::NoMatchingCountries { .. })));
+        let err = generator.write(Path::new("/nonexistent/persons.csv"), Format::Csv, 1, 1);
+        assert!(matches!(err, Err(Error::OutputWrite { .. })));
+    }
+}
@@ -0,0 +1,336 @@
+use std::fs;
+use std::path::Path;
+
+use fake::Rng;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::attributes::{MAX_AGE, MIN_AGE};
+use crate::error::Error;
+use crate::locations::Locations;
+use crate::output::{Format, RowWriter};
+use crate::table::{Column, ColumnType, Row, Value};
+use crate::{finish_name, name_locale, person_seed, PersonOptions};
+
+/// Maximum number of children of a couple, and of a single parent
+const MAX_CHILDREN: u32 = 3;
+const MAX_SINGLE_PARENT_CHILDREN: u32 = 2;
+/// Youngest and oldest age of a parent at the birth of a child
+const MIN_PARENT_AGE: u8 = 18;
+const MAX_PARENT_AGE: u8 = 45;
+/// Maximum age difference between spouses
+const MAX_SPOUSE_AGE_GAP: i16 = 8;
+
+/// A member of a household
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub age: u8,
+}
+
+/// A household at a shared address: one adult, or a married couple, and their children
+#[derive(Debug, Clone, PartialEq)]
+pub struct Household {
+    pub id: u32,
+    pub city: String,
+    pub state: String,
+    pub country: String,
+    pub lat: f64,
+    pub lng: f64,
+    // One adult, or two spouses
+    pub adults: Vec<Member>,
+    pub children: Vec<Member>,
+}
+
+impl Household {
+    fn is_couple(&self) -> bool {
+        self.adults.len() == 2
+    }
+}
+
+/// Construct the household with the given id. Like persons, each household only depends on the
+/// seed and its id. The first adult is married with the probability of their age band, to a spouse
+/// of a similar age, and the children are young enough to live at home and were born when both
+/// parents were between 18 and 45.
+pub fn construct_household(
+    locations: &Locations,
+    options: &PersonOptions,
+    id: u32,
+    seed: u64,
+) -> Household {
+    let mut r = StdRng::seed_from_u64(person_seed(seed, id));
+    let loc = locations.sample(&mut r);
+    let locale = name_locale(&loc.country_code, options);
+    let last_name = locale.last_name(&mut r);
+    let member = |r: &mut StdRng, age: u8| Member {
+        name: finish_name(locale.full_name(&locale.first_name(r), &last_name), options),
+        age,
+    };
+    let age = options.ages.sample(&mut r);
+    let mut adults = vec![member(&mut r, age)];
+    if r.gen_bool(options.marriage_rates.probability(age)) {
+        let gap = r.gen_range(-MAX_SPOUSE_AGE_GAP..=MAX_SPOUSE_AGE_GAP);
+        let spouse_age = (age as i16 + gap).clamp(MIN_AGE as i16, MAX_AGE as i16) as u8;
+        adults.push(member(&mut r, spouse_age));
+    }
+    // Children are under 18, and were born when all their parents were of parenting age
+    let youngest = adults.iter().map(|a| a.age).min().unwrap_or(age);
+    let oldest = adults.iter().map(|a| a.age).max().unwrap_or(age);
+    let max_child_age = (MIN_AGE - 1).min(youngest - MIN_PARENT_AGE);
+    let min_child_age = oldest.saturating_sub(MAX_PARENT_AGE);
+    let mut children = Vec::new();
+    if min_child_age <= max_child_age {
+        let max_children = if adults.len() == 2 {
+            MAX_CHILDREN
+        } else {
+            MAX_SINGLE_PARENT_CHILDREN
+        };
+        for _ in 0..r.gen_range(0..=max_children) {
+            let child_age = r.gen_range(min_child_age..=max_child_age);
+            children.push(member(&mut r, child_age));
+        }
+    }
+    Household {
+        id,
+        city: loc.city.to_string(),
+        state: loc.state.to_string(),
+        country: loc.country.to_string(),
+        lat: loc.lat,
+        lng: loc.lng,
+        adults,
+        children,
+    }
+}
+
+/// Writes households to three files with foreign keys: the households with their address, the
+/// adults with their household and spouse, and the children with their household and parents.
+/// Persons and children are numbered in household order, so their ids don't depend on the chunks.
+pub struct HouseholdWriter {
+    households: RowWriter,
+    persons: RowWriter,
+    children: RowWriter,
+    num_persons: u32,
+    num_children: u32,
+}
+
+impl HouseholdWriter {
+    /// Create the `households`, `persons` and `children` files in `dir`
+    pub fn create(dir: &Path, format: Format) -> Result<Self, Error> {
+        fs::create_dir_all(dir).map_err(|err| Error::OutputWrite {
+            path: dir.to_path_buf(),
+            source: err.into(),
+        })?;
+        let extension = match format {
+            Format::Csv => "csv",
+            Format::Jsonl => "jsonl",
+            Format::Parquet => "parquet",
+        };
+        let create = |name: &str, columns| {
+            RowWriter::create(&dir.join(format!("{name}.{extension}")), format, columns)
+        };
+        Ok(HouseholdWriter {
+            households: create("households", household_columns())?,
+            persons: create("persons", person_columns())?,
+            children: create("children", child_columns())?,
+            num_persons: 0,
+            num_children: 0,
+        })
+    }
+
+    pub fn write(&mut self, households: &[Household]) -> Result<(), Error> {
+        let mut household_rows: Vec<Row> = Vec::with_capacity(households.len());
+        let mut person_rows: Vec<Row> = Vec::new();
+        let mut child_rows: Vec<Row> = Vec::new();
+        for household in households {
+            let household_id = Value::Int(household.id as i64);
+            let size = household.adults.len() + household.children.len();
+            household_rows.push(vec![
+                household_id.clone(),
+                Value::Int(size as i64),
+                Value::Str(household.city.clone()),
+                Value::Str(household.state.clone()),
+                Value::Str(household.country.clone()),
+                Value::Float(household.lat),
+                Value::Float(household.lng),
+            ]);
+            let first_id = self.num_persons as i64 + 1;
+            let adult_ids: Vec<i64> = (first_id..).take(household.adults.len()).collect();
+            for (i, adult) in household.adults.iter().enumerate() {
+                // The spouse of each adult of a couple is the other adult
+                let spouse_id = if household.is_couple() {
+                    Value::Int(adult_ids[1 - i])
+                } else {
+                    Value::Null
+                };
+                person_rows.push(vec![
+                    Value::Int(adult_ids[i]),
+                    household_id.clone(),
+                    Value::Str(adult.name.clone()),
+                    Value::Int(adult.age as i64),
+                    Value::Bool(household.is_couple()),
+                    spouse_id,
+                ]);
+            }
+            for child in household.children.iter() {
+                self.num_children += 1;
+                child_rows.push(vec![
+                    Value::Int(self.num_children as i64),
+                    household_id.clone(),
+                    Value::Str(child.name.clone()),
+                    Value::Int(child.age as i64),
+                    Value::Int(adult_ids[0]),
+                    adult_ids.get(1).map_or(Value::Null, |id| Value::Int(*id)),
+                ]);
+            }
+            self.num_persons += household.adults.len() as u32;
+        }
+        self.households.write(&household_rows)?;
+        self.persons.write(&person_rows)?;
+        self.children.write(&child_rows)
+    }
+
+    /// Finish writing the files, and return the number of persons and children written
+    pub fn finish(self) -> Result<(u32, u32), Error> {
+        self.households.finish()?;
+        self.persons.finish()?;
+        self.children.finish()?;
+        Ok((self.num_persons, self.num_children))
+    }
+}
+
+fn household_columns() -> Vec<Column> {
+    vec![
+        Column::new("householdId", ColumnType::UInt32),
+        Column::new("size", ColumnType::Int),
+        Column::new("city", ColumnType::Str),
+        Column::new("state", ColumnType::Str),
+        Column::new("country", ColumnType::Str),
+        Column::new("lat", ColumnType::Float),
+        Column::new("lng", ColumnType::Float),
+    ]
+}
+
+fn person_columns() -> Vec<Column> {
+    vec![
+        Column::new("id", ColumnType::UInt32),
+        Column::new("householdId", ColumnType::UInt32),
+        Column::new("name", ColumnType::Str),
+        Column::new("age", ColumnType::UInt8),
+        Column::new("isMarried", ColumnType::Bool),
+        Column {
+            nullable: true,
+            ..Column::new("spouseId", ColumnType::UInt32)
+        },
+    ]
+}
+
+fn child_columns() -> Vec<Column> {
+    vec![
+        Column::new("id", ColumnType::UInt32),
+        Column::new("householdId", ColumnType::UInt32),
+        Column::new("name", ColumnType::Str),
+        Column::new("age", ColumnType::UInt8),
+        Column::new("parentId", ColumnType::UInt32),
+        Column {
+            nullable: true,
+            ..Column::new("otherParentId", ColumnType::UInt32)
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locations::test_locations;
+    use arrow_schema::DataType;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn options() -> PersonOptions {
+        PersonOptions {
+            marriage_rates: "18:0.7".parse().unwrap(),
+            local_names: true,
+            ..PersonOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_construct_household() {
+        let (locations, options) = (test_locations(), options());
+        let households: Vec<Household> = (1..=500)
+            .map(|id| construct_household(&locations, &options, id, 42))
+            .collect();
+        assert!(households.iter().any(|h| h.is_couple()));
+        assert!(households.iter().any(|h| !h.is_couple()));
+        assert!(households.iter().any(|h| !h.children.is_empty()));
+        for household in households.iter() {
+            assert!(!household.adults.is_empty() && household.adults.len() <= 2);
+            for child in household.children.iter() {
+                assert!(child.age < MIN_AGE);
+                for parent in household.adults.iter() {
+                    let age_at_birth = parent.age - child.age;
+                    assert!((MIN_PARENT_AGE..=MAX_PARENT_AGE).contains(&age_at_birth));
+                }
+            }
+        }
+        assert_eq!(
+            households[0],
+            construct_household(&locations, &options, 1, 42)
+        );
+    }
+
+    #[test]
+    fn test_write_households() {
+        let (locations, options) = (test_locations(), options());
+        let households: Vec<Household> = (1..=50)
+            .map(|id| construct_household(&locations, &options, id, 42))
+            .collect();
+        let dir = std::env::temp_dir().join(format!("mock_data_{}_households", std::process::id()));
+        let mut writer = HouseholdWriter::create(&dir, Format::Csv).unwrap();
+        // Ids don't depend on how the households are split into chunks
+        writer.write(&households[..20]).unwrap();
+        writer.write(&households[20..]).unwrap();
+        let (num_persons, num_children) = writer.finish().unwrap();
+        let read = |name: &str| -> Vec<Vec<String>> {
+            csv::Reader::from_path(dir.join(name))
+                .unwrap()
+                .records()
+                .map(|r| r.unwrap().iter().map(str::to_string).collect())
+                .collect()
+        };
+        let persons = read("persons.csv");
+        let children = read("children.csv");
+        assert_eq!(read("households.csv").len(), 50);
+        assert_eq!(persons.len() as u32, num_persons);
+        assert_eq!(children.len() as u32, num_children);
+        let person = |id: &str| persons.iter().find(|p| p[0] == id).unwrap();
+        for p in persons.iter() {
+            // Spouses live in the same household and are each other's spouse
+            assert_eq!(p[4] == "true", !p[5].is_empty());
+            if !p[5].is_empty() {
+                let spouse = person(&p[5]);
+                assert_eq!((&spouse[1], &spouse[5]), (&p[1], &p[0]));
+            }
+        }
+        for c in children.iter() {
+            assert_eq!(person(&c[4])[1], c[1]);
+            if !c[5].is_empty() {
+                assert_eq!(person(&c[5])[1], c[1]);
+            }
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Ids, foreign keys and ages have the same types as in the persons file
+        let mut writer = HouseholdWriter::create(&dir, Format::Parquet).unwrap();
+        writer.write(&households).unwrap();
+        writer.finish().unwrap();
+        let file = fs::File::open(dir.join("children.parquet")).unwrap();
+        let schema = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .schema()
+            .clone();
+        let data_type = |name: &str| schema.field_with_name(name).unwrap().data_type().clone();
+        assert_eq!(data_type("id"), DataType::UInt32);
+        assert_eq!(data_type("otherParentId"), DataType::UInt32);
+        assert_eq!(data_type("age"), DataType::UInt8);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,479 @@
+pub mod attributes;
+pub mod cities;
+pub mod defects;
+mod error;
+mod generator;
+pub mod households;
+pub mod locations;
+pub mod names;
+pub mod output;
+pub mod schema;
+pub mod table;
+
+use chrono::NaiveDate;
+use fake::faker::internet::raw::FreeEmailProvider;
+use fake::faker::phone_number::raw::PhoneNumber;
+use fake::locales::EN;
+use fake::{Fake, Rng};
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::attributes::{date_of_birth, AgeDistribution, AgeSpec, MarriageRates};
+use crate::locations::Locations;
+use crate::names::NameLocale;
+use crate::output::{Format, RowWriter};
+use crate::table::{Column, ColumnType, Row, Value};
+
+pub use crate::error::Error;
+pub use crate::generator::Generator;
+
+/// A city that persons can live in
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub city: String,
+    pub state: String,
+    pub country: String,
+    // ISO 3166-1 alpha-2 code of the country
+    pub country_code: String,
+    pub lat: f64,
+    pub lng: f64,
+    pub population: Option<f64>,
+}
+
+/// A generated person profile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Person {
+    pub id: u32,
+    pub name: String,
+    pub age: u8,
+    pub is_married: bool,
+    pub city: String,
+    pub state: String,
+    pub country: String,
+    pub lat: f64,
+    pub lng: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_of_birth: Option<NaiveDate>,
+}
+
+/// Optional person profile fields
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ExtraField {
+    Email,
+    Phone,
+    DateOfBirth,
+}
+
+/// The date on which the dates of birth match the ages by default, fixed so that the persons of a
+/// seed are the same whenever they are generated
+pub const DEFAULT_AS_OF: NaiveDate = match NaiveDate::from_ymd_opt(2025, 1, 1) {
+    Some(date) => date,
+    None => panic!("Invalid default date"),
+};
+
+/// How the attributes of persons are sampled
+#[derive(Debug, Clone)]
+pub struct PersonOptions {
+    pub ages: AgeDistribution,
+    pub marriage_rates: MarriageRates,
+    pub extra_fields: Vec<ExtraField>,
+    // Date on which the dates of birth match the ages
+    pub as_of: NaiveDate,
+    // Whether names are in the locale of the person's country, rather than English
+    pub local_names: bool,
+    // Whether names are converted to ASCII
+    pub ascii_names: bool,
+}
+
+impl PersonOptions {
+    pub fn has(&self, field: ExtraField) -> bool {
+        self.extra_fields.contains(&field)
+    }
+}
+
+impl Default for PersonOptions {
+    fn default() -> Self {
+        PersonOptions {
+            ages: AgeDistribution::new(&AgeSpec::Uniform { min: 18, max: 64 }).unwrap(),
+            marriage_rates: "18:0.5".parse().unwrap(),
+            extra_fields: Vec::new(),
+            as_of: DEFAULT_AS_OF,
+            local_names: false,
+            ascii_names: false,
+        }
+    }
+}
+
+impl Person {
+    /// The columns of the profiles, including the extra fields of `options`
+    pub fn columns(options: &PersonOptions) -> Vec<Column> {
+        let mut columns = vec![
+            Column::new("id", ColumnType::UInt32),
+            Column::new("name", ColumnType::Str),
+            Column::new("age", ColumnType::UInt8),
+            Column::new("isMarried", ColumnType::Bool),
+            Column::new("city", ColumnType::Str),
+            Column::new("state", ColumnType::Str),
+            Column::new("country", ColumnType::Str),
+            Column::new("lat", ColumnType::Float),
+            Column::new("lng", ColumnType::Float),
+        ];
+        if options.has(ExtraField::Email) {
+            columns.push(Column::new("email", ColumnType::Str));
+        }
+        if options.has(ExtraField::Phone) {
+            columns.push(Column::new("phone", ColumnType::Str));
+        }
+        if options.has(ExtraField::DateOfBirth) {
+            columns.push(Column::new("dateOfBirth", ColumnType::Str));
+        }
+        columns
+    }
+
+    /// The row of the profile, with the extra fields it was constructed with
+    pub fn into_row(self) -> Row {
+        let mut row = vec![
+            Value::Int(self.id as i64),
+            Value::Str(self.name),
+            Value::Int(self.age as i64),
+            Value::Bool(self.is_married),
+            Value::Str(self.city),
+            Value::Str(self.state),
+            Value::Str(self.country),
+            Value::Float(self.lat),
+            Value::Float(self.lng),
+        ];
+        row.extend(self.email.map(Value::Str));
+        row.extend(self.phone.map(Value::Str));
+        row.extend(self.date_of_birth.map(|d| Value::Str(d.to_string())));
+        row
+    }
+}
+
+pub fn convert_unicode_to_ascii(s: &str) -> String {
+    // First ensure that the unicode string is normalized to NFKD form
+    s.nfkd().filter(|c| c.is_ascii()).collect::<String>()
+}
+
+/// The seed of a person's random number generator, so that each person only depends on the base
+/// seed and its id. A base seed of 0 seeds each person with its id.
+pub(crate) fn person_seed(seed: u64, id: u32) -> u64 {
+    // Spread the base seeds apart so that nearby seeds don't produce overlapping ids
+    seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ id as u64
+}
+
+/// An email address derived from a person's name, e.g. `jane.doe42@gmail.com`
+fn email_from_name<R: Rng>(name: &str, r: &mut R) -> String {
+    let user = convert_unicode_to_ascii(name)
+        .split_whitespace()
+        .map(|part| {
+            part.chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(".");
+    let user = if user.is_empty() { "user" } else { &user };
+    let number: u16 = r.gen_range(1..100);
+    let provider: String = FreeEmailProvider(EN).fake_with_rng(r);
+    format!("{user}{number}@{provider}")
+}
+
+/// The locale of a person's names: the locale of their country if `options.local_names` is set.
+/// Names in non-Latin scripts can't be converted to ASCII, so with `options.ascii_names` they are
+/// replaced by English names.
+pub(crate) fn name_locale(country_code: &str, options: &PersonOptions) -> NameLocale {
+    let locale = if options.local_names {
+        NameLocale::for_country(country_code)
+    } else {
+        NameLocale::En
+    };
+    if options.ascii_names && !locale.is_latin() {
+        NameLocale::En
+    } else {
+        locale
+    }
+}
+
+/// A name, converted to ASCII if `options.ascii_names` is set
+pub(crate) fn finish_name(name: String, options: &PersonOptions) -> String {
+    if options.ascii_names {
+        convert_unicode_to_ascii(&name)
+    } else {
+        name
+    }
+}
+
+pub fn construct_person(
+    locations: &Locations,
+    options: &PersonOptions,
+    id: u32,
+    seed: u64,
+) -> Person {
+    let mut r = StdRng::seed_from_u64(person_seed(seed, id));
+    let loc = locations.sample(&mut r);
+    let name = finish_name(
+        name_locale(&loc.country_code, options).name(&mut r),
+        options,
+    );
+    let age = options.ages.sample(&mut r);
+    let is_married = r.gen_bool(options.marriage_rates.probability(age));
+    // The extra fields are sampled last, so that they don't change the other fields
+    let email = options
+        .has(ExtraField::Email)
+        .then(|| email_from_name(&name, &mut r));
+    let phone = options
+        .has(ExtraField::Phone)
+        .then(|| PhoneNumber(EN).fake_with_rng(&mut r));
+    let date_of_birth = options
+        .has(ExtraField::DateOfBirth)
+        .then(|| date_of_birth(&mut r, age, options.as_of));
+    Person {
+        id,
+        name,
+        age,
+        is_married,
+        city: loc.city.to_string(),
+        state: loc.state.to_string(),
+        country: loc.country.to_string(),
+        lat: loc.lat,
+        lng: loc.lng,
+        email,
+        phone,
+        date_of_birth,
+    }
+}
+
+/// Construct the records with ids in `ids` in parallel, in id order
+fn generate_chunk<T, G>(ids: RangeInclusive<u32>, construct: &G) -> Vec<T>
+where
+    T: Send,
+    G: Fn(u32) -> T + Sync,
+{
+    ids.into_par_iter().map(construct).collect()
+}
+
+/// Construct `count` records in chunks of `chunk_size`, calling `consume` on each chunk in id
+/// order. Each chunk is constructed in parallel while the previous one is consumed, so at most two
+/// chunks are in memory at any time. Because each record only depends on the seed and its id, the
+/// records do not depend on the number of threads or the chunk size.
+pub fn generate<T, G, F, E>(
+    count: u32,
+    chunk_size: u32,
+    construct: G,
+    mut consume: F,
+) -> Result<(), E>
+where
+    T: Send + Sync,
+    G: Fn(u32) -> T + Sync,
+    F: FnMut(&[T]) -> Result<(), E> + Send,
+    E: Send,
+{
+    let chunk_size = chunk_size.max(1);
+    let chunk_ids = |start: u32| start..=start.saturating_add(chunk_size - 1).min(count);
+    if count == 0 {
+        return Ok(());
+    }
+    let mut ids = chunk_ids(1);
+    let mut chunk = generate_chunk(ids.clone(), &construct);
+    loop {
+        let next_ids = (*ids.end() < count).then(|| chunk_ids(ids.end() + 1));
+        let (consumed, next_chunk) = rayon::join(
+            || consume(&chunk),
+            || next_ids.clone().map(|ids| generate_chunk(ids, &construct)),
+        );
+        consumed?;
+        match (next_ids, next_chunk) {
+            (Some(next_ids), Some(next_chunk)) => {
+                ids = next_ids;
+                chunk = next_chunk;
+            }
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// Generate `count` rows with `construct` and write them to `output_path`
+pub fn write_rows<G>(
+    columns: Vec<Column>,
+    construct: G,
+    count: u32,
+    chunk_size: u32,
+    output_path: &Path,
+    format: Format,
+) -> Result<(), Error>
+where
+    G: Fn(u32) -> Row + Sync,
+{
+    let mut writer = RowWriter::create(output_path, format, columns)?;
+    generate(count, chunk_size, construct, |chunk| writer.write(chunk))?;
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cities::{read_cities, CitiesFormat, CityColumns};
+    use crate::locations::{test_cities, test_locations};
+
+    #[test]
+    fn test_convert_unicode_to_ascii() {
+        let name_list = [
+            ("São Paulo", "Sao Paulo"),
+            ("İstanbul", "Istanbul"),
+            ("Mahārāshtra", "Maharashtra"),
+            ("Středočeský Kraj", "Stredocesky Kraj"),
+            ("Dar‘ā", "Dara"),
+            ("Île-de-France", "Ile-de-France"),
+        ];
+        for (unicode, ascii) in name_list.iter() {
+            assert_eq!(super::convert_unicode_to_ascii(unicode), *ascii);
+        }
+    }
+
+    #[test]
+    fn test_read_cities() {
+        let path = Path::new("../data/worldcities.csv");
+        let columns = CityColumns::new(CitiesFormat::Worldcities);
+        let cities = read_cities(path, CitiesFormat::Worldcities, &columns).unwrap();
+        assert_eq!(cities.locations.len(), 44691);
+    }
+
+    #[test]
+    fn test_construct_person() {
+        let age_lower = 18;
+        let age_upper = 65;
+        let path = Path::new("../data/worldcities.csv");
+        let columns = CityColumns::new(CitiesFormat::Worldcities);
+        let cities = read_cities(path, CitiesFormat::Worldcities, &columns).unwrap();
+        let locations = Locations::new(cities.locations, &[]).unwrap();
+        let person = super::construct_person(&locations, &PersonOptions::default(), 1, 0);
+        assert!(person.id > 0);
+        assert!(person.name.split(' ').count() > 1);
+        assert!(age_lower <= person.age && person.age <= age_upper);
+    }
+
+    #[test]
+    fn test_construct_person_seed() {
+        let locations = test_locations();
+        let options = PersonOptions::default();
+        let name = |id, seed| construct_person(&locations, &options, id, seed).name;
+        // The same seed always produces the same person, and a base seed of 0 seeds by id
+        assert_eq!(name(1, 42), name(1, 42));
+        assert_ne!(name(1, 42), name(1, 43));
+        assert_eq!(person_seed(0, 7), 7);
+        // The coordinates are those of the person's city
+        let person = construct_person(&locations, &options, 1, 42);
+        let location = test_cities()
+            .into_iter()
+            .find(|c| c.city == person.city)
+            .map(|c| (c.lat, c.lng));
+        assert_eq!(location, Some((person.lat, person.lng)));
+        // The default dates of birth don't depend on the day they are generated
+        let options = PersonOptions {
+            extra_fields: vec![ExtraField::DateOfBirth],
+            ..PersonOptions::default()
+        };
+        let person = construct_person(&locations, &options, 1, 42);
+        let born = person.date_of_birth.unwrap();
+        assert_eq!(DEFAULT_AS_OF.years_since(born), Some(person.age as u32));
+    }
+
+    #[test]
+    fn test_construct_person_extra_fields() {
+        let locations = test_locations();
+        let options = PersonOptions {
+            ages: AgeDistribution::new(&"normal:70,5".parse().unwrap()).unwrap(),
+            marriage_rates: "18:0,60:1".parse().unwrap(),
+            extra_fields: vec![ExtraField::DateOfBirth, ExtraField::Email],
+            as_of: NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+            ..PersonOptions::default()
+        };
+        let columns = Person::columns(&options);
+        assert_eq!(columns.len(), 11);
+        assert_eq!(columns[9].name, "email");
+        assert_eq!(columns[10].name, "dateOfBirth");
+        for id in 1..=100 {
+            let person = construct_person(&locations, &options, id, 42);
+            assert_eq!(person.is_married, person.age >= 60);
+            let email = person.email.clone().unwrap();
+            let first_name = person.name.split(' ').next().unwrap().to_lowercase();
+            assert!(email.starts_with(&format!("{first_name}.")), "{email}");
+            assert!(person.phone.is_none());
+            let born = person.date_of_birth.unwrap();
+            assert_eq!(options.as_of.years_since(born), Some(person.age as u32));
+            // The extra fields don't change the other fields
+            let plain = construct_person(
+                &locations,
+                &PersonOptions {
+                    extra_fields: Vec::new(),
+                    ..options.clone()
+                },
+                id,
+                42,
+            );
+            assert_eq!((plain.name, plain.age), (person.name.clone(), person.age));
+            assert_eq!(person.into_row().len(), columns.len());
+        }
+    }
+
+    #[test]
+    fn test_construct_person_local_names() {
+        let locations = test_locations();
+        let local = PersonOptions {
+            local_names: true,
+            ..PersonOptions::default()
+        };
+        let ascii = PersonOptions {
+            ascii_names: true,
+            ..local.clone()
+        };
+        for id in 1..=100 {
+            let person = construct_person(&locations, &local, id, 42);
+            // Japanese and Arabic names are in non-Latin scripts
+            let non_latin = matches!(person.country.as_str(), "Japan" | "Jordan");
+            assert!(!non_latin || !person.name.is_ascii(), "{}", person.name);
+            let person = construct_person(&locations, &ascii, id, 42);
+            assert!(person.name.is_ascii() && person.name.contains(' '));
+        }
+    }
+
+    #[test]
+    fn test_generate_independent_of_threads_and_chunks() {
+        let locations = test_locations();
+        let options = PersonOptions::default();
+        let generate_with = |threads, chunk_size| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            let mut ids_and_names = Vec::new();
+            pool.install(|| {
+                let construct = |id| construct_person(&locations, &options, id, 42);
+                generate(100, chunk_size, construct, |chunk: &[Person]| {
+                    ids_and_names.extend(chunk.iter().map(|p| (p.id, p.name.clone())));
+                    Ok::<_, Error>(())
+                })
+            })
+            .unwrap();
+            ids_and_names
+        };
+        let expected = generate_with(1, 100);
+        assert_eq!(expected.len(), 100);
+        assert!(expected.iter().map(|p| p.0).eq(1..=100));
+        assert_eq!(generate_with(4, 7), expected);
+        assert_eq!(generate_with(2, 1), expected);
+    }
+}
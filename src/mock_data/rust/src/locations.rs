@@ -0,0 +1,176 @@
+use fake::Rng;
+
+use crate::error::Error;
+use crate::Location;
+
+/// Walker's alias table, to sample indices in proportion to their weights in constant time
+#[derive(Debug, Clone)]
+pub struct AliasTable {
+    // Probability of keeping each index rather than its alias
+    probabilities: Vec<f64>,
+    aliases: Vec<usize>,
+}
+
+impl AliasTable {
+    /// Build the table with Vose's method. Weights must be non-negative, with a positive sum.
+    pub fn new(weights: &[f64]) -> Result<Self, Error> {
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err(Error::InvalidWeights(
+                "Weights must be finite and non-negative".to_string(),
+            ));
+        }
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return Err(Error::InvalidWeights(
+                "At least one weight must be positive".to_string(),
+            ));
+        }
+        let n = weights.len();
+        // Weights scaled so that their mean is 1
+        let mut scaled: Vec<f64> = weights.iter().map(|w| w * n as f64 / total).collect();
+        let mut probabilities = vec![1.0; n];
+        let mut aliases: Vec<usize> = (0..n).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..n).partition(|&i| scaled[i] < 1.0);
+        while let (Some(s), Some(&l)) = (small.pop(), large.last()) {
+            // The remainder of index `s` is filled by index `l`
+            probabilities[s] = scaled[s];
+            aliases[s] = l;
+            scaled[l] += scaled[s] - 1.0;
+            if scaled[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // Any index left over (in either list, because of rounding errors) is always kept
+        Ok(AliasTable {
+            probabilities,
+            aliases,
+        })
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        let i = rng.gen_range(0..self.probabilities.len());
+        if rng.gen::<f64>() < self.probabilities[i] {
+            i
+        } else {
+            self.aliases[i]
+        }
+    }
+}
+
+/// The locations to sample from, weighted by their population
+pub struct Locations {
+    cities: Vec<Location>,
+    table: AliasTable,
+}
+
+impl Locations {
+    /// Keep the cities in one of `countries` (by name or ISO code, case-insensitively), or all of
+    /// them if `countries` is empty. Cities without a population are given a population of 1.
+    pub fn new(cities: Vec<Location>, countries: &[String]) -> Result<Self, Error> {
+        let cities: Vec<Location> = if countries.is_empty() {
+            cities
+        } else {
+            cities
+                .into_iter()
+                .filter(|city| {
+                    countries.iter().any(|c| {
+                        c.eq_ignore_ascii_case(&city.country)
+                            || c.eq_ignore_ascii_case(&city.country_code)
+                    })
+                })
+                .collect()
+        };
+        if cities.is_empty() && countries.is_empty() {
+            return Err(Error::NoCities);
+        }
+        if cities.is_empty() {
+            return Err(Error::NoMatchingCountries {
+                countries: countries.to_vec(),
+            });
+        }
+        let weights: Vec<f64> = cities
+            .iter()
+            .map(|city| city.population.unwrap_or(1.0).max(0.0))
+            .collect();
+        let table = AliasTable::new(&weights)?;
+        Ok(Locations { cities, table })
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> &Location {
+        &self.cities[self.table.sample(rng)]
+    }
+}
+
+/// Cities shared by the tests: Tokyo, Recife, Paris and Madaba, with their coordinates and
+/// populations, and their name as their state
+#[cfg(test)]
+pub(crate) fn test_cities() -> Vec<Location> {
+    [
+        ("Tokyo", "Japan", "JP", 35.6897, 139.6922, 37_732_000.0),
+        ("Recife", "Brazil", "BR", -8.0539, -34.8808, 1_653_461.0),
+        ("Paris", "France", "FR", 48.8567, 2.3522, 11_060_000.0),
+        ("Madaba", "Jordan", "JO", 31.7167, 35.8, 60_000.0),
+    ]
+    .into_iter()
+    .map(|(city, country, code, lat, lng, population)| Location {
+        city: city.to_string(),
+        state: city.to_string(),
+        country: country.to_string(),
+        country_code: code.to_string(),
+        lat,
+        lng,
+        population: Some(population),
+    })
+    .collect()
+}
+
+/// The locations of all the `test_cities`
+#[cfg(test)]
+pub(crate) fn test_locations() -> Locations {
+    Locations::new(test_cities(), &[]).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_alias_table() {
+        let table = AliasTable::new(&[1.0, 0.0, 3.0, 6.0]).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut counts = [0i32; 4];
+        for _ in 0..100_000 {
+            counts[table.sample(&mut rng)] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        for (count, expected) in counts.iter().zip([10_000, 0, 30_000, 60_000]) {
+            assert!((*count - expected).abs() < 1000, "{counts:?}");
+        }
+        assert!(matches!(
+            AliasTable::new(&[0.0, 0.0]),
+            Err(Error::InvalidWeights(_))
+        ));
+        assert!(AliasTable::new(&[1.0, -1.0]).is_err());
+    }
+
+    #[test]
+    fn test_locations_filter_and_weights() {
+        let mut cities = test_cities();
+        cities.push(Location {
+            city: "Kumano".to_string(),
+            population: None,
+            ..cities[0].clone()
+        });
+        let locations = Locations::new(cities, &["jp".to_string()]).unwrap();
+        assert_eq!(locations.cities.len(), 2);
+        let mut rng = StdRng::seed_from_u64(1);
+        // A city with a population of 1 is practically never sampled next to Tokyo
+        assert!((0..1000).all(|_| locations.sample(&mut rng).city == "Tokyo"));
+        assert!(matches!(Locations::new(vec![], &[]), Err(Error::NoCities)));
+        let err = Locations::new(test_cities(), &["Germany".to_string()]);
+        assert!(matches!(err, Err(Error::NoMatchingCountries { .. })));
+    }
+}
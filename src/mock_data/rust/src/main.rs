@@ -1,148 +1,135 @@
-use fake::faker::name::raw::Name;
-use fake::locales::EN;
-use fake::{Fake, Rng};
-use rand::seq::IteratorRandom;
-use rand::{rngs::StdRng, SeedableRng};
-use serde::{Deserialize, Serialize};
-use std::{fs, io::Write, path::Path};
-use unicode_normalization::UnicodeNormalization;
+mod cli;
 
-struct Location {
-    city: String,
-    state: String,
-    country: String,
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Person {
-    id: u32,
-    name: String,
-    age: u8,
-    is_married: bool,
-    city: String,
-    state: String,
-    country: String,
-}
-
-fn convert_unicode_to_ascii(s: &str) -> String {
-    // First ensure that the unicode string is normalized to NFKD form
-    s.nfkd().filter(|c| c.is_ascii()).collect::<String>()
-}
+use anyhow::Result;
+use chrono::Local;
+use clap::Parser;
+use mock_data::attributes::AgeDistribution;
+use mock_data::cities::{read_cities, CityColumns};
+use mock_data::defects::{applicable_kinds, manifest_path, DefectInjector, ManifestWriter};
+use mock_data::households::{construct_household, HouseholdWriter};
+use mock_data::locations::Locations;
+use mock_data::output::RowWriter;
+use mock_data::schema::Schema;
+use mock_data::{generate, write_rows, Generator, Location, PersonOptions};
+use std::time::Instant;
 
-// Read from CSV file
-fn read_cities(path: &Path) -> Vec<Location> {
-    let mut rdr =
-        csv::Reader::from_path(path).expect("Cannot read cities CSV file. Please check the path.");
-    let mut cities: Vec<Location> = Vec::new();
-    for record in rdr.records() {
-        let record = record.expect("Did not obtain valid record from CSV file.");
-        let city = &record[1];
-        let state = convert_unicode_to_ascii(&record[7]);
-        let country = &record[4];
-        cities.push(Location {
-            city: city.to_string(),
-            state: state.to_string(),
-            country: country.to_string(),
-        });
-    }
-    cities
-}
-
-fn construct_person(locations: &[Location], id: u32) -> Person {
-    let mut r = StdRng::seed_from_u64(id as u64);
-    let loc = locations.iter().choose(&mut r);
-    let name: String = Name(EN).fake_with_rng(&mut r);
-    let age: u8 = r.gen_range(18..65);
-    let is_married = r.gen_bool(0.5);
-    // Return a vector
-    Person {
-        id,
-        name,
-        age,
-        is_married,
-        city: loc.unwrap().city.to_string(),
-        state: loc.unwrap().state.to_string(),
-        country: loc.unwrap().country.to_string(),
-    }
-}
+use crate::cli::Args;
 
-fn write_csv(output_path: &Path, result: Vec<u8>) {
-    let mut file = fs::File::create(output_path).expect("Unable to create file for writer");
-    file.write_all(result.as_slice())
-        .expect("Unable to write to output CSV file");
+/// The options of the persons given on the command line
+fn person_options(args: &Args) -> Result<PersonOptions> {
+    Ok(PersonOptions {
+        ages: AgeDistribution::new(&args.ages)?,
+        marriage_rates: args.marriage_rates.clone(),
+        extra_fields: args.extra_fields.clone(),
+        as_of: args.as_of.unwrap_or_else(|| Local::now().date_naive()),
+        local_names: args.local_names,
+        ascii_names: args.ascii_names,
+    })
 }
 
-fn run(locations: &[Location], limit: u32) {
-    let mut profiles: Vec<Person> = Vec::new();
-    for id in 1..=limit {
-        let person = construct_person(locations, id);
-        profiles.push(person);
+/// Read the locations of the cities file given on the command line, and warn about skipped rows
+fn load_cities(args: &Args) -> Result<Vec<Location>> {
+    let mut columns = CityColumns::new(args.cities_format);
+    if let Some(mapping) = &args.city_columns {
+        columns = columns.with_mapping(mapping)?;
     }
-    let mut wtr = csv::Writer::from_writer(vec![]);
-    // Serialize the data to CSV and write it to file
-    for person in profiles.iter() {
-        wtr.serialize(person)
-            .expect("Unable to serialize output CSV");
+    let cities = read_cities(&args.cities, args.cities_format, &columns)?;
+    if cities.num_invalid > 0 || cities.num_duplicates > 0 {
+        eprintln!(
+            "Skipped {} invalid and {} duplicate rows of {:?}",
+            cities.num_invalid, cities.num_duplicates, args.cities
+        );
     }
-    let result = wtr.into_inner().expect("Unable to construct CSV output");
-    let output_path = Path::new("../data/persons.csv");
-    write_csv(output_path, result);
+    Ok(cities.locations)
 }
 
-fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    let path = Path::new("../data/worldcities.csv");
-    let locations = read_cities(path);
-    match args.len() {
-        // If no args are provided, length is 1 because the first arg is the program name
-        1 => {
-            run(&locations, 10);
-            println!("No limit provided. Defaulting to generating 10 person profiles.");
-        }
-        _ => {
-            let limit = args[1].parse::<u32>().unwrap();
-            run(&locations, limit);
-            println!("Generating {limit} person profiles.");
-        }
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()?;
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_convert_unicode_to_ascii() {
-        let name_list = [
-            ("São Paulo", "Sao Paulo"),
-            ("İstanbul", "Istanbul"),
-            ("Mahārāshtra", "Maharashtra"),
-            ("Středočeský Kraj", "Stredocesky Kraj"),
-            ("Dar‘ā", "Dara"),
-            ("Île-de-France", "Ile-de-France"),
-        ];
-        for (unicode, ascii) in name_list.iter() {
-            assert_eq!(super::convert_unicode_to_ascii(unicode), *ascii);
+    let start = Instant::now();
+    let seed = args.seed;
+    let kind = if let Some(path) = &args.schema {
+        let schema = Schema::load(path)?;
+        // The cities file is only needed by schemas with location fields
+        let locations = if schema.uses_locations() {
+            Some(Locations::new(load_cities(&args)?, &args.countries)?)
+        } else {
+            None
+        };
+        write_rows(
+            schema.columns(),
+            |id| schema.generate_row(locations.as_ref(), id, seed),
+            args.count,
+            args.chunk_size,
+            &args.output,
+            args.format,
+        )?;
+        format!("records in {:?}", args.output)
+    } else if let Some(dir) = &args.households {
+        let generator = Generator::new(
+            load_cities(&args)?,
+            &args.countries,
+            person_options(&args)?,
+            seed,
+        )?;
+        let (locations, options) = (generator.locations(), generator.options());
+        let mut writer = HouseholdWriter::create(dir, args.format)?;
+        generate(
+            args.count,
+            args.chunk_size,
+            |id| construct_household(locations, options, id, seed),
+            |chunk| writer.write(chunk),
+        )?;
+        let (num_persons, num_children) = writer.finish()?;
+        format!("households with {num_persons} persons and {num_children} children in {dir:?}")
+    } else {
+        let generator = Generator::new(
+            load_cities(&args)?,
+            &args.countries,
+            person_options(&args)?,
+            seed,
+        )?;
+        if let Some(rate) = args.defect_rate {
+            let columns = generator.columns();
+            let kinds = if args.defects.is_empty() {
+                applicable_kinds(&columns)
+            } else {
+                args.defects.clone()
+            };
+            let injector = DefectInjector::new(rate, &kinds, columns, seed)?;
+            let mut writer = RowWriter::create(&args.output, args.format, injector.columns())?;
+            let manifest_path = manifest_path(&args.output);
+            let mut manifest = ManifestWriter::create(&manifest_path)?;
+            generate(
+                args.count,
+                args.chunk_size,
+                |id| injector.inject(id, generator.person(id).into_row()),
+                |chunk| {
+                    writer.write(chunk)?;
+                    manifest.write(chunk)
+                },
+            )?;
+            writer.finish()?;
+            let num_defects = manifest.finish()?;
+            format!(
+                "person profiles in {:?} with {num_defects} defects listed in {manifest_path:?}",
+                args.output
+            )
+        } else {
+            generator.write(&args.output, args.format, args.count, args.chunk_size)?;
+            format!("person profiles in {:?}", args.output)
         }
-    }
-
-    #[test]
-    fn test_read_cities() {
-        let path = Path::new("../data/worldcities.csv");
-        let cities = super::read_cities(path);
-        assert_eq!(cities.len(), 44691);
-    }
-
-    #[test]
-    fn test_construct_person() {
-        let age_lower = 18;
-        let age_upper = 65;
-        let path = Path::new("../data/worldcities.csv");
-        let locations = super::read_cities(path);
-        let person = super::construct_person(&locations, 1);
-        assert!(person.id > 0);
-        assert!(person.name.split(' ').count() > 1);
-        assert!(age_lower <= person.age && person.age <= age_upper);
-    }
+    };
+    println!(
+        "Generated {} {kind} with seed {} in {:.3} sec using {} threads",
+        args.count,
+        args.seed,
+        start.elapsed().as_secs_f64(),
+        rayon::current_num_threads()
+    );
+    Ok(())
 }
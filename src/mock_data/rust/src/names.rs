@@ -0,0 +1,109 @@
+use fake::faker::name::raw::{FirstName, LastName, Name};
+use fake::locales::{AR_SA, EN, FR_FR, JA_JP, PT_BR, ZH_CN, ZH_TW};
+use fake::{Fake, Rng};
+
+/// The `fake` locales that names can be generated in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NameLocale {
+    En,
+    FrFr,
+    JaJp,
+    PtBr,
+    ZhCn,
+    ZhTw,
+    ArSa,
+}
+
+impl NameLocale {
+    /// The locale of the names of a country, by ISO 3166-1 alpha-2 code. Countries without a
+    /// supported locale get English names.
+    pub fn for_country(country_code: &str) -> Self {
+        match country_code.to_ascii_uppercase().as_str() {
+            "FR" | "MC" => NameLocale::FrFr,
+            "JP" => NameLocale::JaJp,
+            "BR" | "PT" | "AO" | "MZ" => NameLocale::PtBr,
+            "CN" => NameLocale::ZhCn,
+            "TW" | "HK" | "MO" => NameLocale::ZhTw,
+            "SA" | "AE" | "QA" | "KW" | "BH" | "OM" | "YE" | "JO" | "IQ" | "SY" | "LB" | "PS"
+            | "EG" | "LY" | "TN" | "DZ" | "MA" | "SD" => NameLocale::ArSa,
+            _ => NameLocale::En,
+        }
+    }
+
+    /// Whether names of the locale are written in the Latin alphabet, and can therefore be
+    /// converted to ASCII by stripping their diacritics
+    pub fn is_latin(self) -> bool {
+        matches!(self, NameLocale::En | NameLocale::FrFr | NameLocale::PtBr)
+    }
+
+    /// A full name, in the order customary in the locale
+    pub fn name<R: Rng + ?Sized>(self, rng: &mut R) -> String {
+        match self {
+            NameLocale::En => Name(EN).fake_with_rng(rng),
+            NameLocale::FrFr => Name(FR_FR).fake_with_rng(rng),
+            NameLocale::JaJp => Name(JA_JP).fake_with_rng(rng),
+            NameLocale::PtBr => Name(PT_BR).fake_with_rng(rng),
+            NameLocale::ZhCn => Name(ZH_CN).fake_with_rng(rng),
+            NameLocale::ZhTw => Name(ZH_TW).fake_with_rng(rng),
+            NameLocale::ArSa => Name(AR_SA).fake_with_rng(rng),
+        }
+    }
+
+    pub fn first_name<R: Rng + ?Sized>(self, rng: &mut R) -> String {
+        match self {
+            NameLocale::En => FirstName(EN).fake_with_rng(rng),
+            NameLocale::FrFr => FirstName(FR_FR).fake_with_rng(rng),
+            NameLocale::JaJp => FirstName(JA_JP).fake_with_rng(rng),
+            NameLocale::PtBr => FirstName(PT_BR).fake_with_rng(rng),
+            NameLocale::ZhCn => FirstName(ZH_CN).fake_with_rng(rng),
+            NameLocale::ZhTw => FirstName(ZH_TW).fake_with_rng(rng),
+            NameLocale::ArSa => FirstName(AR_SA).fake_with_rng(rng),
+        }
+    }
+
+    pub fn last_name<R: Rng + ?Sized>(self, rng: &mut R) -> String {
+        match self {
+            NameLocale::En => LastName(EN).fake_with_rng(rng),
+            NameLocale::FrFr => LastName(FR_FR).fake_with_rng(rng),
+            NameLocale::JaJp => LastName(JA_JP).fake_with_rng(rng),
+            NameLocale::PtBr => LastName(PT_BR).fake_with_rng(rng),
+            NameLocale::ZhCn => LastName(ZH_CN).fake_with_rng(rng),
+            NameLocale::ZhTw => LastName(ZH_TW).fake_with_rng(rng),
+            NameLocale::ArSa => LastName(AR_SA).fake_with_rng(rng),
+        }
+    }
+
+    /// A full name from a first and last name, in the same order as `name`
+    pub fn full_name(self, first_name: &str, last_name: &str) -> String {
+        match self {
+            NameLocale::JaJp => format!("{last_name} {first_name}"),
+            NameLocale::ZhCn | NameLocale::ZhTw => format!("{last_name}{first_name}"),
+            _ => format!("{first_name} {last_name}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_name_locales() {
+        assert_eq!(NameLocale::for_country("jp"), NameLocale::JaJp);
+        assert_eq!(NameLocale::for_country("BR"), NameLocale::PtBr);
+        assert_eq!(NameLocale::for_country("HK"), NameLocale::ZhTw);
+        assert_eq!(NameLocale::for_country("JO"), NameLocale::ArSa);
+        assert_eq!(NameLocale::for_country("US"), NameLocale::En);
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!(!NameLocale::JaJp.name(&mut rng).is_ascii());
+        assert!(!NameLocale::ZhCn.name(&mut rng).is_ascii());
+        assert!(NameLocale::En.name(&mut rng).is_ascii());
+        assert_eq!(NameLocale::JaJp.full_name("奏多", "小野"), "小野 奏多");
+        assert_eq!(NameLocale::ZhCn.full_name("明志", "姜"), "姜明志");
+        assert_eq!(
+            NameLocale::FrFr.full_name("Donatien", "Hardy"),
+            "Donatien Hardy"
+        );
+    }
+}
@@ -0,0 +1,351 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use arrow_array::builder::{
+    BooleanBuilder, Float64Builder, Int64Builder, StringBuilder, UInt32Builder, UInt8Builder,
+};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use parquet::arrow::ArrowWriter;
+
+use crate::error::Error;
+use crate::table::{Column, ColumnType, JsonRow, Value};
+
+/// Output file format
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Format {
+    Csv,
+    Jsonl,
+    Parquet,
+}
+
+/// Writes generated rows to a file in one of the supported formats, in batches
+pub struct RowWriter {
+    path: PathBuf,
+    columns: Vec<Column>,
+    schema: SchemaRef,
+    output: Output,
+}
+
+enum Output {
+    Csv(csv::Writer<BufWriter<File>>),
+    Jsonl(BufWriter<File>),
+    Parquet(ArrowWriter<File>),
+}
+
+/// The Arrow schema of the columns, with the same field names as the CSV header
+pub fn arrow_schema(columns: &[Column]) -> SchemaRef {
+    let fields: Vec<Field> = columns
+        .iter()
+        .map(|column| {
+            let data_type = match column.column_type {
+                ColumnType::Bool => DataType::Boolean,
+                ColumnType::Int => DataType::Int64,
+                ColumnType::UInt8 => DataType::UInt8,
+                ColumnType::UInt32 => DataType::UInt32,
+                ColumnType::Float => DataType::Float64,
+                ColumnType::Str => DataType::Utf8,
+            };
+            Field::new(&column.name, data_type, column.nullable)
+        })
+        .collect();
+    Arc::new(Schema::new(fields))
+}
+
+/// Errors of the underlying writers, reported as the source of an `Error::OutputWrite`
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+fn to_array<R: AsRef<[Value]>>(
+    column: &Column,
+    rows: &[R],
+    index: usize,
+) -> Result<ArrayRef, String> {
+    let values = rows.iter().map(|row| &row.as_ref()[index]);
+    let mismatch = |value: &Value| {
+        format!(
+            "Invalid value {value:?} for {:?} column {:?}",
+            column.column_type, column.name
+        )
+    };
+    let array: ArrayRef = match column.column_type {
+        ColumnType::Bool => {
+            let mut builder = BooleanBuilder::with_capacity(rows.len());
+            for value in values {
+                match value {
+                    Value::Bool(b) => builder.append_value(*b),
+                    Value::Null => builder.append_null(),
+                    _ => return Err(mismatch(value)),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        ColumnType::Int => {
+            let mut builder = Int64Builder::with_capacity(rows.len());
+            for value in values {
+                match value {
+                    Value::Int(i) => builder.append_value(*i),
+                    Value::Null => builder.append_null(),
+                    _ => return Err(mismatch(value)),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        ColumnType::UInt8 => {
+            let mut builder = UInt8Builder::with_capacity(rows.len());
+            for value in values {
+                match value {
+                    Value::Int(i) => {
+                        builder.append_value(u8::try_from(*i).map_err(|_| mismatch(value))?)
+                    }
+                    Value::Null => builder.append_null(),
+                    _ => return Err(mismatch(value)),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        ColumnType::UInt32 => {
+            let mut builder = UInt32Builder::with_capacity(rows.len());
+            for value in values {
+                match value {
+                    Value::Int(i) => {
+                        builder.append_value(u32::try_from(*i).map_err(|_| mismatch(value))?)
+                    }
+                    Value::Null => builder.append_null(),
+                    _ => return Err(mismatch(value)),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        ColumnType::Float => {
+            let mut builder = Float64Builder::with_capacity(rows.len());
+            for value in values {
+                match value {
+                    Value::Float(f) => builder.append_value(*f),
+                    Value::Int(i) => builder.append_value(*i as f64),
+                    Value::Null => builder.append_null(),
+                    _ => return Err(mismatch(value)),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        ColumnType::Str => {
+            let mut builder = StringBuilder::with_capacity(rows.len(), rows.len() * 16);
+            // Arrow strings have 32-bit offsets, which the builder panics on overflowing
+            let mut num_bytes = 0usize;
+            for value in values {
+                match value {
+                    Value::Null => builder.append_null(),
+                    value => {
+                        let field = value.to_field();
+                        num_bytes += field.len();
+                        if num_bytes > i32::MAX as usize {
+                            return Err(format!(
+                                "Column {:?} has too much text for one batch",
+                                column.name
+                            ));
+                        }
+                        builder.append_value(field)
+                    }
+                }
+            }
+            Arc::new(builder.finish())
+        }
+    };
+    Ok(array)
+}
+
+/// The rows as an Arrow record batch with the given schema, which must match the columns
+pub fn to_record_batch<R: AsRef<[Value]>>(
+    schema: SchemaRef,
+    columns: &[Column],
+    rows: &[R],
+) -> Result<RecordBatch, Error> {
+    let arrays = columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| to_array(column, rows, index))
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(|message| Error::RecordBatch(message.into()))?;
+    RecordBatch::try_new(schema, arrays).map_err(|err| Error::RecordBatch(err.into()))
+}
+
+fn output_error(path: &Path, source: BoxError) -> Error {
+    Error::OutputWrite {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+impl Output {
+    fn create(
+        path: &Path,
+        format: Format,
+        columns: &[Column],
+        schema: SchemaRef,
+    ) -> Result<Self, BoxError> {
+        let file = File::create(path)?;
+        let output = match format {
+            Format::Csv => {
+                let mut wtr = csv::Writer::from_writer(BufWriter::new(file));
+                wtr.write_record(columns.iter().map(|c| &c.name))?;
+                Output::Csv(wtr)
+            }
+            Format::Jsonl => Output::Jsonl(BufWriter::new(file)),
+            Format::Parquet => Output::Parquet(ArrowWriter::try_new(file, schema, None)?),
+        };
+        Ok(output)
+    }
+}
+
+impl RowWriter {
+    pub fn create(path: &Path, format: Format, columns: Vec<Column>) -> Result<Self, Error> {
+        let schema = arrow_schema(&columns);
+        let output = Output::create(path, format, &columns, schema.clone())
+            .map_err(|err| output_error(path, err))?;
+        Ok(RowWriter {
+            path: path.to_path_buf(),
+            columns,
+            schema,
+            output,
+        })
+    }
+
+    /// Write a batch of rows, or of anything that holds a row
+    pub fn write<R: AsRef<[Value]>>(&mut self, rows: &[R]) -> Result<(), Error> {
+        self.write_rows(rows)
+            .map_err(|err| output_error(&self.path, err))
+    }
+
+    fn write_rows<R: AsRef<[Value]>>(&mut self, rows: &[R]) -> Result<(), BoxError> {
+        match &mut self.output {
+            Output::Csv(wtr) => {
+                for row in rows {
+                    wtr.write_record(row.as_ref().iter().map(Value::to_field))?;
+                }
+            }
+            Output::Jsonl(wtr) => {
+                for row in rows {
+                    let row = JsonRow {
+                        columns: &self.columns,
+                        row: row.as_ref(),
+                    };
+                    serde_json::to_writer(&mut *wtr, &row)?;
+                    writeln!(wtr)?;
+                }
+            }
+            // Each batch becomes (part of) a row group
+            Output::Parquet(wtr) => {
+                wtr.write(&to_record_batch(self.schema.clone(), &self.columns, rows)?)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Flush the remaining output. Parquet files are only valid once their footer is written here.
+    pub fn finish(self) -> Result<(), Error> {
+        let finish = || -> Result<(), BoxError> {
+            match self.output {
+                Output::Csv(mut wtr) => wtr.flush()?,
+                Output::Jsonl(mut wtr) => wtr.flush()?,
+                Output::Parquet(wtr) => {
+                    wtr.close()?;
+                }
+            }
+            Ok(())
+        };
+        finish().map_err(|err| output_error(&self.path, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::Row;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::fs;
+
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::new("id", ColumnType::UInt32),
+            Column::new("name", ColumnType::Str),
+            Column::new("isMarried", ColumnType::Bool),
+            Column {
+                nullable: true,
+                ..Column::new("score", ColumnType::Float)
+            },
+        ]
+    }
+
+    fn rows() -> Vec<Row> {
+        vec![
+            vec![
+                Value::Int(1),
+                Value::Str("Megan Chang".to_string()),
+                Value::Bool(false),
+                Value::Float(0.5),
+            ],
+            vec![
+                Value::Int(2),
+                Value::Str("Billy Sheppard, Jr.".to_string()),
+                Value::Bool(true),
+                Value::Null,
+            ],
+        ]
+    }
+
+    fn write(format: Format, name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("mock_data_{}_{name}", std::process::id()));
+        let mut writer = RowWriter::create(&path, format, columns()).unwrap();
+        writer.write(&rows()).unwrap();
+        writer.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn test_write_csv_and_jsonl() {
+        let path = write(Format::Csv, "rows.csv");
+        let csv = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            csv.lines().collect::<Vec<_>>(),
+            [
+                "id,name,isMarried,score",
+                "1,Megan Chang,false,0.5",
+                "2,\"Billy Sheppard, Jr.\",true,"
+            ]
+        );
+        fs::remove_file(path).unwrap();
+
+        let path = write(Format::Jsonl, "rows.jsonl");
+        let jsonl = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            jsonl.lines().nth(1).unwrap(),
+            r#"{"id":2,"name":"Billy Sheppard, Jr.","isMarried":true,"score":null}"#
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_write_parquet() {
+        let path = write(Format::Parquet, "rows.parquet");
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(batches[0].num_rows(), 2);
+        assert_eq!(
+            batches[0].schema().fields(),
+            arrow_schema(&columns()).fields()
+        );
+        assert_eq!(batches[0].column(3).null_count(), 1);
+        fs::remove_file(path).unwrap();
+
+        // Values out of the range of a narrow column are rejected
+        let columns = [Column::new("age", ColumnType::UInt8)];
+        let rows = [[Value::Int(-1)]];
+        assert!(to_record_batch(arrow_schema(&columns), &columns, &rows).is_err());
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use chrono::{Duration, NaiveDate};
+use fake::faker::company::raw::CompanyName;
+use fake::faker::internet::raw::SafeEmail;
+use fake::faker::lorem::raw::{Sentence, Word};
+use fake::faker::name::raw::{FirstName, LastName, Name};
+use fake::faker::phone_number::raw::PhoneNumber;
+use fake::locales::EN;
+use fake::{Fake, Rng};
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::locations::Locations;
+use crate::person_seed;
+use crate::table::{Column, ColumnType, Row, Value};
+
+/// The kind of values generated for a field
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FieldKind {
+    /// The row's id, from 1 to the number of rows
+    Id,
+    Integer {
+        min: i64,
+        max: i64,
+    },
+    Float {
+        min: f64,
+        max: f64,
+        // Number of decimal places to round to
+        decimals: Option<u32>,
+    },
+    Boolean {
+        // Probability of `true`
+        #[serde(default = "default_probability")]
+        probability: f64,
+    },
+    /// One of a list of values, optionally weighted
+    Choice {
+        values: Vec<String>,
+        weights: Option<Vec<f64>>,
+    },
+    /// A date between `start` and `end` (inclusive), as `YYYY-MM-DD`
+    Date {
+        start: NaiveDate,
+        end: NaiveDate,
+    },
+    FirstName,
+    LastName,
+    Name,
+    Email,
+    Phone,
+    Company,
+    Word,
+    Sentence,
+    /// The city, state, country, latitude or longitude of a location sampled from the cities file,
+    /// weighted by population. All the location fields of a row come from the same location.
+    City,
+    State,
+    Country,
+    Lat,
+    Lng,
+}
+
+fn default_probability() -> f64 {
+    0.5
+}
+
+/// A field of the schema file
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldSpec {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: FieldKind,
+    // Share of the rows in which the field is null
+    #[serde(default)]
+    pub null_ratio: f64,
+}
+
+/// A schema file: the list of fields of the records to generate, in column order
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct SchemaSpec {
+    pub fields: Vec<FieldSpec>,
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidSchema {
+        path: None,
+        message,
+    }
+}
+
+/// A validated schema, ready to generate rows
+#[derive(Debug, Clone)]
+pub struct Schema {
+    fields: Vec<FieldSpec>,
+    // Sampling distributions of the weighted choice fields, by field index
+    weights: Vec<Option<WeightedIndex<f64>>>,
+}
+
+impl Schema {
+    /// Load a schema from a TOML or JSON file, depending on its extension
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path).map_err(|source| Error::SchemaFile {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let invalid = |message: String| Error::InvalidSchema {
+            path: Some(path.to_path_buf()),
+            message,
+        };
+        let spec: SchemaSpec = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&contents).map_err(|err| invalid(err.to_string()))?,
+            Some("json") => {
+                serde_json::from_str(&contents).map_err(|err| invalid(err.to_string()))?
+            }
+            _ => {
+                return Err(invalid(
+                    "The schema file must have a .toml or .json extension".to_string(),
+                ))
+            }
+        };
+        Schema::new(spec).map_err(|err| match err {
+            Error::InvalidSchema { message, .. } => invalid(message),
+            err => err,
+        })
+    }
+
+    pub fn new(spec: SchemaSpec) -> Result<Self, Error> {
+        if spec.fields.is_empty() {
+            return Err(invalid("At least one field must be defined".to_string()));
+        }
+        let mut names = HashSet::new();
+        let mut weights = Vec::with_capacity(spec.fields.len());
+        for field in spec.fields.iter() {
+            let name = &field.name;
+            if !names.insert(name) {
+                return Err(invalid(format!("Duplicate field name {name:?}")));
+            }
+            if !(0.0..=1.0).contains(&field.null_ratio) {
+                return Err(invalid(format!(
+                    "The null_ratio of field {name:?} must be between 0 and 1"
+                )));
+            }
+            let mut field_weights = None;
+            match &field.kind {
+                FieldKind::Integer { min, max } if min > max => {
+                    return Err(invalid(format!(
+                        "The min of field {name:?} is greater than its max"
+                    )))
+                }
+                FieldKind::Float { min, max, .. } if !min.is_finite() || !max.is_finite() => {
+                    return Err(invalid(format!(
+                        "The min and max of field {name:?} must be finite numbers"
+                    )))
+                }
+                FieldKind::Float { min, max, .. } if min > max => {
+                    return Err(invalid(format!(
+                        "The min of field {name:?} is greater than its max"
+                    )))
+                }
+                // Sampling is uniform over max - min, which must not overflow
+                FieldKind::Float { min, max, .. } if !(max - min).is_finite() => {
+                    return Err(invalid(format!("The range of field {name:?} is too large")))
+                }
+                FieldKind::Date { start, end } if start > end => {
+                    return Err(invalid(format!(
+                        "The start of field {name:?} is after its end"
+                    )))
+                }
+                FieldKind::Boolean { probability } if !(0.0..=1.0).contains(probability) => {
+                    return Err(invalid(format!(
+                        "The probability of field {name:?} must be between 0 and 1"
+                    )))
+                }
+                FieldKind::Choice { values, .. } if values.is_empty() => {
+                    return Err(invalid(format!(
+                        "Field {name:?} has no values to choose from"
+                    )))
+                }
+                FieldKind::Choice {
+                    values,
+                    weights: Some(w),
+                } => {
+                    if w.len() != values.len() {
+                        return Err(invalid(format!(
+                            "Field {name:?} must have one weight per value"
+                        )));
+                    }
+                    field_weights = Some(WeightedIndex::new(w).map_err(|err| {
+                        invalid(format!("Invalid weights for field {name:?}: {err}"))
+                    })?);
+                }
+                _ => {}
+            }
+            weights.push(field_weights);
+        }
+        Ok(Schema {
+            fields: spec.fields,
+            weights,
+        })
+    }
+
+    pub fn columns(&self) -> Vec<Column> {
+        self.fields
+            .iter()
+            .map(|field| {
+                let column_type = match field.kind {
+                    FieldKind::Id | FieldKind::Integer { .. } => ColumnType::Int,
+                    FieldKind::Float { .. } | FieldKind::Lat | FieldKind::Lng => ColumnType::Float,
+                    FieldKind::Boolean { .. } => ColumnType::Bool,
+                    _ => ColumnType::Str,
+                };
+                Column {
+                    nullable: field.null_ratio > 0.0,
+                    ..Column::new(&field.name, column_type)
+                }
+            })
+            .collect()
+    }
+
+    /// Whether any field is sampled from the cities file
+    pub fn uses_locations(&self) -> bool {
+        self.fields.iter().any(|field| {
+            matches!(
+                field.kind,
+                FieldKind::City
+                    | FieldKind::State
+                    | FieldKind::Country
+                    | FieldKind::Lat
+                    | FieldKind::Lng
+            )
+        })
+    }
+
+    /// Generate the row with the given id. Like persons, each row only depends on the seed and
+    /// its id.
+    pub fn generate_row(&self, locations: Option<&Locations>, id: u32, seed: u64) -> Row {
+        let mut r = StdRng::seed_from_u64(person_seed(seed, id));
+        let location = locations
+            .filter(|_| self.uses_locations())
+            .map(|locations| locations.sample(&mut r));
+        self.fields
+            .iter()
+            .zip(self.weights.iter())
+            .map(|(field, weights)| {
+                if field.null_ratio > 0.0 && r.gen_bool(field.null_ratio) {
+                    return Value::Null;
+                }
+                match &field.kind {
+                    FieldKind::Id => Value::Int(id as i64),
+                    FieldKind::Integer { min, max } => Value::Int(r.gen_range(*min..=*max)),
+                    FieldKind::Float { min, max, decimals } => {
+                        let value = r.gen_range(*min..=*max);
+                        Value::Float(match decimals {
+                            Some(decimals) => {
+                                let scale = 10f64.powi(*decimals as i32);
+                                (value * scale).round() / scale
+                            }
+                            None => value,
+                        })
+                    }
+                    FieldKind::Boolean { probability } => Value::Bool(r.gen_bool(*probability)),
+                    FieldKind::Choice { values, .. } => {
+                        let index = match weights {
+                            Some(weights) => weights.sample(&mut r),
+                            None => r.gen_range(0..values.len()),
+                        };
+                        Value::Str(values[index].clone())
+                    }
+                    FieldKind::Date { start, end } => {
+                        let days = r.gen_range(0..=(*end - *start).num_days());
+                        Value::Str((*start + Duration::days(days)).to_string())
+                    }
+                    FieldKind::FirstName => Value::Str(FirstName(EN).fake_with_rng(&mut r)),
+                    FieldKind::LastName => Value::Str(LastName(EN).fake_with_rng(&mut r)),
+                    FieldKind::Name => Value::Str(Name(EN).fake_with_rng(&mut r)),
+                    FieldKind::Email => Value::Str(SafeEmail(EN).fake_with_rng(&mut r)),
+                    FieldKind::Phone => Value::Str(PhoneNumber(EN).fake_with_rng(&mut r)),
+                    FieldKind::Company => Value::Str(CompanyName(EN).fake_with_rng(&mut r)),
+                    FieldKind::Word => Value::Str(Word(EN).fake_with_rng(&mut r)),
+                    FieldKind::Sentence => Value::Str(Sentence(EN, 4..10).fake_with_rng(&mut r)),
+                    FieldKind::City => location.map_or(Value::Null, |l| Value::Str(l.city.clone())),
+                    FieldKind::State => {
+                        location.map_or(Value::Null, |l| Value::Str(l.state.clone()))
+                    }
+                    FieldKind::Country => {
+                        location.map_or(Value::Null, |l| Value::Str(l.country.clone()))
+                    }
+                    FieldKind::Lat => location.map_or(Value::Null, |l| Value::Float(l.lat)),
+                    FieldKind::Lng => location.map_or(Value::Null, |l| Value::Float(l.lng)),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_schema() {
+        let schema = Schema::load(Path::new("../schemas/customers.toml")).unwrap();
+        let columns = schema.columns();
+        assert_eq!(columns[0], Column::new("customer_id", ColumnType::Int));
+        assert!(schema.uses_locations());
+        let schema = Schema::load(Path::new("../schemas/orders.json")).unwrap();
+        assert!(!schema.uses_locations());
+        assert!(schema.columns().iter().any(|c| c.nullable));
+    }
+
+    #[test]
+    fn test_validate_schema() {
+        let spec = |json: &str| -> SchemaSpec { serde_json::from_str(json).unwrap() };
+        assert!(Schema::new(spec(r#"{"fields": []}"#)).is_err());
+        assert!(Schema::new(spec(
+            r#"{"fields": [{"name": "a", "kind": "integer", "min": 5, "max": 1}]}"#
+        ))
+        .is_err());
+        assert!(Schema::new(spec(
+            r#"{"fields": [{"name": "a", "kind": "id"}, {"name": "a", "kind": "word"}]}"#
+        ))
+        .is_err());
+        assert!(Schema::new(spec(
+            r#"{"fields": [{"name": "a", "kind": "choice", "values": ["x"], "weights": [1, 2]}]}"#
+        ))
+        .is_err());
+        assert!(Schema::new(spec(
+            r#"{"fields": [{"name": "a", "kind": "word", "null_ratio": 1.5}]}"#
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_validate_float_range() {
+        let path =
+            std::env::temp_dir().join(format!("mock_data_{}_floats.toml", std::process::id()));
+        for (min, max) in [
+            ("nan", "1.0"),
+            ("0.0", "inf"),
+            ("-inf", "0.0"),
+            ("-1.7e308", "1.7e308"),
+        ] {
+            let contents =
+                format!("[[fields]]\nname = \"x\"\nkind = \"float\"\nmin = {min}\nmax = {max}\n");
+            fs::write(&path, contents).unwrap();
+            let err = Schema::load(&path).unwrap_err();
+            assert!(
+                matches!(&err, Error::InvalidSchema { path: Some(_), message }
+                    if message.contains("field \"x\"")),
+                "{min}..={max}: {err}"
+            );
+        }
+        fs::write(
+            &path,
+            "[[fields]]\nname = \"x\"\nkind = \"float\"\nmin = -1e9\nmax = 1e9\n",
+        )
+        .unwrap();
+        assert!(Schema::load(&path).is_ok());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_generate_row() {
+        let spec: SchemaSpec = toml::from_str(
+            r#"
+            [[fields]]
+            name = "id"
+            kind = "id"
+
+            [[fields]]
+            name = "quantity"
+            kind = "integer"
+            min = 1
+            max = 3
+
+            [[fields]]
+            name = "status"
+            kind = "choice"
+            values = ["new", "shipped"]
+            weights = [0, 1]
+
+            [[fields]]
+            name = "shipped_on"
+            kind = "date"
+            start = "2024-01-01"
+            end = "2024-01-31"
+
+            [[fields]]
+            name = "note"
+            kind = "sentence"
+            null_ratio = 1.0
+            "#,
+        )
+        .unwrap();
+        let schema = Schema::new(spec).unwrap();
+        for id in 1..=50 {
+            let row = schema.generate_row(None, id, 42);
+            assert_eq!(row[0], Value::Int(id as i64));
+            assert!(matches!(row[1], Value::Int(1..=3)));
+            assert_eq!(row[2], Value::Str("shipped".to_string()));
+            assert!(row[3].to_field().starts_with("2024-01-"));
+            assert_eq!(row[4], Value::Null);
+            assert_eq!(row, schema.generate_row(None, id, 42));
+        }
+    }
+}
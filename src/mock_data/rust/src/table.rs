@@ -0,0 +1,84 @@
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+/// A field value of a generated row
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+impl Value {
+    /// The value as a CSV field, where nulls are empty
+    pub fn to_field(&self) -> String {
+        match self {
+            Value::Null => String::new(),
+            Value::Bool(b) => b.to_string(),
+            Value::Int(i) => i.to_string(),
+            Value::Float(f) => f.to_string(),
+            Value::Str(s) => s.clone(),
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_none(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Int(i) => serializer.serialize_i64(*i),
+            Value::Float(f) => serializer.serialize_f64(*f),
+            Value::Str(s) => serializer.serialize_str(s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnType {
+    Bool,
+    Int,
+    /// Integers stored as unsigned 8-bit or 32-bit integers in Arrow and Parquet, e.g. ages and ids
+    UInt8,
+    UInt32,
+    Float,
+    Str,
+}
+
+/// A named, typed column of the generated rows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub column_type: ColumnType,
+    pub nullable: bool,
+}
+
+impl Column {
+    pub fn new(name: &str, column_type: ColumnType) -> Self {
+        Column {
+            name: name.to_string(),
+            column_type,
+            nullable: false,
+        }
+    }
+}
+
+/// One generated record, with one value per column
+pub type Row = Vec<Value>;
+
+/// A row serialized as a JSON object, with its fields in column order
+pub struct JsonRow<'a> {
+    pub columns: &'a [Column],
+    pub row: &'a [Value],
+}
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for (column, value) in self.columns.iter().zip(self.row) {
+            map.serialize_entry(&column.name, value)?;
+        }
+        map.end()
+    }
+}
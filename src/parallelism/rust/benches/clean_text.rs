@@ -0,0 +1,128 @@
+use std::fs::File;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rayon::prelude::*;
+use regex::{Captures, Regex};
+
+use parallelism::lexicon::{count_terms, default_lexicons, Lexicon};
+use parallelism::text::{clean_text, for_each_token};
+use parallelism::{process_record, Record};
+
+/// The original implementation, kept here as the baseline for comparison
+mod legacy {
+    use super::*;
+
+    pub fn clean_text(text: &str) -> String {
+        let pattern1 = Regex::new(r"([’'])(s|d|ll)").unwrap();
+        // Replace pattern with text
+        let matched = pattern1.replace_all(text, |capture: &Captures| match &capture[2] {
+            "s" => " is",
+            "d" => " had",
+            "ll" => " will",
+            _ => "<unk>",
+        });
+        // Remove non-alphabetic characters
+        let pattern2 = Regex::new(r"[^a-zA-Z\s]").unwrap();
+        let clean_text = pattern2.replace_all(&matched, "");
+        let result: String = clean_text.to_lowercase();
+        result
+    }
+
+    pub fn count_gendered_pronouns(tokens: Vec<&str>) -> (usize, usize) {
+        let num_male_pronouns = tokens
+            .par_iter()
+            .filter(|&x| *x == "he" || *x == "him" || *x == "his")
+            .count();
+        let num_female_pronouns = tokens
+            .par_iter()
+            .filter(|&x| *x == "she" || *x == "her" || *x == "hers")
+            .count();
+        (num_male_pronouns, num_female_pronouns)
+    }
+
+    pub fn process_record(record: &Record) -> (usize, usize) {
+        let result: String = clean_text(&record.content);
+        let tokens: Vec<&str> = result.split_whitespace().collect();
+        count_gendered_pronouns(tokens)
+    }
+}
+
+fn load_records() -> Vec<Record> {
+    ["articles1.csv", "articles2.csv", "articles3.csv"]
+        .iter()
+        .flat_map(|name| {
+            let file = File::open(format!("../data/{name}")).expect("Unable to read from CSV");
+            csv::Reader::from_reader(file)
+                .into_deserialize::<Record>()
+                .collect::<Result<Vec<_>, _>>()
+                .expect("Unable to parse CSV")
+        })
+        .collect()
+}
+
+fn count_tokens(text: &str, lexicons: &[Lexicon]) -> Vec<usize> {
+    let cleaned = clean_text(text);
+    let tokens: Vec<&str> = cleaned.split_whitespace().collect();
+    count_terms(&tokens, lexicons)
+}
+
+fn bench_clean_text(c: &mut Criterion) {
+    let records = load_records();
+    let lexicons = default_lexicons();
+    let num_bytes: usize = records.iter().map(|r| r.content.len()).sum();
+
+    let mut group = c.benchmark_group("clean_text");
+    group.throughput(Throughput::Bytes(num_bytes as u64));
+    group.bench_function("legacy", |b| {
+        b.iter(|| {
+            for record in records.iter() {
+                black_box(legacy::clean_text(&record.content));
+            }
+        })
+    });
+    group.bench_function("clean_text", |b| {
+        b.iter(|| {
+            for record in records.iter() {
+                black_box(clean_text(&record.content));
+            }
+        })
+    });
+    group.bench_function("for_each_token", |b| {
+        b.iter(|| {
+            for record in records.iter() {
+                let mut n = 0;
+                for_each_token(&record.content, |_| n += 1);
+                black_box(n);
+            }
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("process_record");
+    group.throughput(Throughput::Elements(records.len() as u64));
+    group.bench_function("legacy", |b| {
+        b.iter(|| {
+            for record in records.iter() {
+                black_box(legacy::process_record(record));
+            }
+        })
+    });
+    group.bench_function("clean_text", |b| {
+        b.iter(|| {
+            for record in records.iter() {
+                black_box(count_tokens(&record.content, &lexicons));
+            }
+        })
+    });
+    group.bench_function("single_pass", |b| {
+        b.iter(|| {
+            for record in records.iter() {
+                black_box(process_record(record, &lexicons));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_clean_text);
+criterion_main!(benches);
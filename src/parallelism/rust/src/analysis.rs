@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::io;
+
+use rayon::prelude::*;
+
+use crate::pipeline::process_chunks;
+use crate::text::{tokenize, Token};
+use crate::Record;
+
+/// Number of occurrences of a term, and number of articles it occurs in
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TermCount {
+    pub count: usize,
+    pub document_frequency: usize,
+}
+
+/// A term ranked by its TF-IDF score within a publication
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopTerm {
+    pub term: String,
+    pub count: usize,
+    pub tf_idf: f64,
+}
+
+/// Corpus-wide term statistics, computed by map-reduce over the articles
+#[derive(Debug, Clone, Default)]
+pub struct TermStats {
+    pub num_documents: usize,
+    pub unigrams: HashMap<String, TermCount>,
+    pub bigrams: HashMap<String, TermCount>,
+    // Unigram counts per publication
+    pub publications: HashMap<String, HashMap<String, usize>>,
+}
+
+fn add_document(totals: &mut HashMap<String, TermCount>, counts: HashMap<String, usize>) {
+    for (term, count) in counts {
+        let total = totals.entry(term).or_default();
+        total.count += count;
+        total.document_frequency += 1;
+    }
+}
+
+fn merge_counts(totals: &mut HashMap<String, TermCount>, other: HashMap<String, TermCount>) {
+    for (term, other) in other {
+        let total = totals.entry(term).or_default();
+        total.count += other.count;
+        total.document_frequency += other.document_frequency;
+    }
+}
+
+impl TermStats {
+    pub fn add_record(&mut self, record: &Record) {
+        let mut unigrams: HashMap<String, usize> = HashMap::new();
+        let mut bigrams: HashMap<String, usize> = HashMap::new();
+        // Bigrams do not span sentence boundaries
+        let mut previous = String::new();
+        tokenize(&record.content, |token| match token {
+            Token::Word(word) => {
+                *unigrams.entry(word.to_string()).or_default() += 1;
+                if !previous.is_empty() {
+                    *bigrams.entry(format!("{previous} {word}")).or_default() += 1;
+                }
+                previous.clear();
+                previous.push_str(word);
+            }
+            Token::SentenceEnd(_) => previous.clear(),
+        });
+
+        let publication = self
+            .publications
+            .entry(record.publication.trim().to_string())
+            .or_default();
+        for (term, count) in unigrams.iter() {
+            *publication.entry(term.clone()).or_default() += count;
+        }
+        add_document(&mut self.unigrams, unigrams);
+        add_document(&mut self.bigrams, bigrams);
+        self.num_documents += 1;
+    }
+
+    pub fn merge(mut self, other: TermStats) -> Self {
+        self.num_documents += other.num_documents;
+        merge_counts(&mut self.unigrams, other.unigrams);
+        merge_counts(&mut self.bigrams, other.bigrams);
+        for (name, other_counts) in other.publications {
+            let counts = self.publications.entry(name).or_default();
+            for (term, count) in other_counts {
+                *counts.entry(term).or_default() += count;
+            }
+        }
+        self
+    }
+
+    /// Smoothed inverse document frequency of a unigram: `ln((1 + N) / (1 + df))`, which is zero
+    /// for terms that occur in every article
+    pub fn idf(&self, term: &str) -> f64 {
+        let df = self.unigrams.get(term).map_or(0, |t| t.document_frequency);
+        ((1 + self.num_documents) as f64 / (1 + df) as f64).ln()
+    }
+
+    /// The `top_n` terms of each publication, ranked by TF-IDF, where the term frequency is the
+    /// share of the publication's tokens and the IDF is computed over all articles
+    pub fn top_terms_by_publication(&self, top_n: usize) -> Vec<(String, Vec<TopTerm>)> {
+        let mut result: Vec<(String, Vec<TopTerm>)> = self
+            .publications
+            .par_iter()
+            .map(|(name, counts)| {
+                let num_tokens: usize = counts.values().sum();
+                let mut terms: Vec<TopTerm> = counts
+                    .iter()
+                    .map(|(term, &count)| {
+                        let tf = count as f64 / num_tokens as f64;
+                        TopTerm {
+                            term: term.clone(),
+                            count,
+                            tf_idf: tf * self.idf(term),
+                        }
+                    })
+                    .collect();
+                terms.sort_by(|a, b| {
+                    b.tf_idf
+                        .total_cmp(&a.tf_idf)
+                        .then_with(|| a.term.cmp(&b.term))
+                });
+                terms.truncate(top_n);
+                (name.clone(), terms)
+            })
+            .collect();
+        result.sort_by(|a, b| a.0.cmp(&b.0));
+        result
+    }
+}
+
+/// Compute term statistics for all records in `reader`, streaming it in chunks
+pub fn analyze_csv<R: io::Read + Send>(
+    reader: R,
+    chunk_size: usize,
+) -> Result<TermStats, csv::Error> {
+    let mut stats = TermStats::default();
+    process_chunks(
+        csv::Reader::from_reader(reader).into_deserialize(),
+        chunk_size,
+        |chunk| {
+            chunk
+                .par_iter()
+                .fold(TermStats::default, |mut stats, record| {
+                    stats.add_record(record);
+                    stats
+                })
+                .reduce(TermStats::default, TermStats::merge)
+        },
+        |chunk_stats| {
+            stats = std::mem::take(&mut stats).merge(chunk_stats);
+            Ok(())
+        },
+    )?;
+    Ok(stats)
+}
+
+/// Sort terms by descending count (ties broken alphabetically) and keep those with at least
+/// `min_count` occurrences
+pub fn ranked_terms(
+    terms: &HashMap<String, TermCount>,
+    min_count: usize,
+) -> Vec<(&str, TermCount)> {
+    let mut ranked: Vec<(&str, TermCount)> = terms
+        .iter()
+        .filter(|(_, t)| t.count >= min_count)
+        .map(|(term, t)| (term.as_str(), *t))
+        .collect();
+    ranked.par_sort_unstable_by(|a, b| b.1.count.cmp(&a.1.count).then_with(|| a.0.cmp(b.0)));
+    ranked
+}
+
+/// Write ranked term frequencies as CSV, with `rank,term,count,document_frequency` fields
+pub fn write_frequencies<W: io::Write>(
+    writer: W,
+    terms: &HashMap<String, TermCount>,
+    min_count: usize,
+) -> Result<(), csv::Error> {
+    let mut wtr = csv::Writer::from_writer(writer);
+    wtr.write_record(["rank", "term", "count", "document_frequency"])?;
+    for (rank, (term, t)) in ranked_terms(terms, min_count).iter().enumerate() {
+        wtr.write_record([
+            (rank + 1).to_string(),
+            term.to_string(),
+            t.count.to_string(),
+            t.document_frequency.to_string(),
+        ])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Write the top terms of each publication as CSV, with `publication,rank,term,count,tf_idf` fields
+pub fn write_top_terms<W: io::Write>(
+    writer: W,
+    stats: &TermStats,
+    top_n: usize,
+) -> Result<(), csv::Error> {
+    let mut wtr = csv::Writer::from_writer(writer);
+    wtr.write_record(["publication", "rank", "term", "count", "tf_idf"])?;
+    for (publication, terms) in stats.top_terms_by_publication(top_n) {
+        for (rank, top_term) in terms.iter().enumerate() {
+            wtr.write_record([
+                publication.clone(),
+                (rank + 1).to_string(),
+                top_term.term.clone(),
+                top_term.count.to_string(),
+                format!("{:.6}", top_term.tf_idf),
+            ])?;
+        }
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    fn record(publication: &str, content: &str) -> Record {
+        Record {
+            id: 1,
+            publication: publication.to_string(),
+            author: "A".to_string(),
+            date: "2017-01-01".to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    fn stats(records: &[Record]) -> TermStats {
+        records.iter().fold(TermStats::default(), |mut stats, r| {
+            stats.add_record(r);
+            stats
+        })
+    }
+
+    #[test]
+    fn test_add_record() {
+        let stats = stats(&[
+            record("NYT", "The cat sat on the mat. The end"),
+            record("CNN", "The dog sat."),
+        ]);
+        assert_eq!(stats.num_documents, 2);
+        let the = stats.unigrams["the"];
+        assert_eq!((the.count, the.document_frequency), (4, 2));
+        let sat = stats.bigrams["cat sat"];
+        assert_eq!((sat.count, sat.document_frequency), (1, 1));
+        assert_eq!(stats.bigrams["the mat"].count, 1);
+        assert!(!stats.bigrams.contains_key("mat the"));
+        assert_eq!(stats.bigrams["the end"].count, 1);
+        assert_eq!(stats.publications["NYT"]["the"], 3);
+        let ranked = ranked_terms(&stats.unigrams, 2);
+        assert_eq!(
+            ranked.iter().map(|r| r.0).collect::<Vec<_>>(),
+            ["the", "sat"]
+        );
+    }
+
+    #[test]
+    fn test_top_terms_by_publication() {
+        let stats = stats(&[
+            record("NYT", "the senate voted the senate"),
+            record("CNN", "the storm hit"),
+        ]);
+        let top = stats.top_terms_by_publication(1);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].0, "CNN");
+        // "the" occurs in every article, so it is never the most distinctive term
+        assert_eq!(top[0].1[0].term, "hit");
+        assert_eq!(top[1].1[0].term, "senate");
+        assert_eq!(top[1].1[0].count, 2);
+    }
+
+    #[test]
+    fn test_analyze_csv_matches_sequential() {
+        let file = File::open("../data/articles1.csv").unwrap();
+        let parallel = analyze_csv(file, 3).unwrap();
+        let mut rdr = csv::Reader::from_reader(File::open("../data/articles1.csv").unwrap());
+        let records: Vec<Record> = rdr.deserialize().collect::<Result<_, _>>().unwrap();
+        let sequential = stats(&records);
+        assert_eq!(parallel.num_documents, sequential.num_documents);
+        assert_eq!(parallel.unigrams, sequential.unigrams);
+        assert_eq!(parallel.bigrams, sequential.bigrams);
+        assert_eq!(parallel.publications, sequential.publications);
+    }
+}
@@ -0,0 +1,98 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use parallelism::pipeline::DEFAULT_CHUNK_SIZE;
+
+#[derive(Debug, Parser)]
+#[clap(version, about = "Count lexicon terms in news articles in parallel")]
+pub struct Args {
+    #[clap(
+        default_value = "../data/articles*.csv",
+        help = "Input CSV files, directories or glob patterns"
+    )]
+    pub inputs: Vec<String>,
+
+    #[clap(
+        short,
+        long,
+        help = "Directory to write the processed files to. Default: next to each input file"
+    )]
+    pub output_dir: Option<PathBuf>,
+
+    #[clap(
+        short,
+        long,
+        help = "Number of worker threads. Default: number of CPU cores"
+    )]
+    pub threads: Option<usize>,
+
+    #[clap(
+        short,
+        long,
+        help = "JSON file of lexicons to count. Default: male and female pronouns"
+    )]
+    pub lexicons: Option<PathBuf>,
+
+    #[clap(
+        short,
+        long,
+        help = "Directory to write summaries grouped by publication, author and month to"
+    )]
+    pub summary_dir: Option<PathBuf>,
+
+    #[clap(
+        short,
+        long,
+        help = "Directory to write the sentences containing lexicon terms to, as JSONL files"
+    )]
+    pub context_dir: Option<PathBuf>,
+
+    #[clap(
+        short,
+        long,
+        help = "Run the word frequency, n-gram and TF-IDF analysis instead of counting lexicon terms, writing the results to this directory"
+    )]
+    pub analysis_dir: Option<PathBuf>,
+
+    #[clap(
+        long,
+        default_value_t = 25,
+        help = "Number of top terms per publication in the analysis"
+    )]
+    pub top_n: usize,
+
+    #[clap(
+        long,
+        default_value_t = 1,
+        help = "Minimum count of the unigrams and bigrams written by the analysis"
+    )]
+    pub min_count: usize,
+
+    #[clap(long, default_value_t = DEFAULT_CHUNK_SIZE, help = "Number of records per chunk")]
+    pub chunk_size: usize,
+
+    #[clap(
+        long,
+        default_value_t = 60,
+        help = "Minimum number of seconds between two checkpoints of each file's progress"
+    )]
+    pub checkpoint_interval: u64,
+
+    #[clap(
+        short,
+        long,
+        help = "Resume from the checkpoints of a previous run, skipping the records already processed"
+    )]
+    pub resume: bool,
+
+    #[clap(
+        short,
+        long,
+        help = "JSON file to write the run time and throughput metrics to"
+    )]
+    pub metrics: Option<PathBuf>,
+
+    #[clap(long, help = "Do not show the progress bar")]
+    pub no_progress: bool,
+}
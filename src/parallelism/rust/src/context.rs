@@ -0,0 +1,197 @@
+use serde::Serialize;
+
+use crate::lexicon::Lexicon;
+use crate::text::{tokenize, Token};
+use crate::Record;
+
+/// An occurrence of a lexicon term within a sentence
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TermMatch {
+    pub lexicon: String,
+    pub term: String,
+    // Position of the term among the sentence's cleaned words
+    pub word_index: usize,
+}
+
+/// A sentence that contains at least one lexicon term
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SentenceContext {
+    // Position of the sentence within the article
+    pub sentence_index: usize,
+    // Byte offsets of the sentence within the article's content
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    pub matches: Vec<TermMatch>,
+    // Capitalized words in the sentence, as a crude proxy for named entities
+    pub entities: Vec<String>,
+}
+
+/// The sentences of an article that contain lexicon terms, written as one JSONL line per article
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ArticleContext {
+    pub id: u32,
+    pub sentences: Vec<SentenceContext>,
+}
+
+fn trim_word(word: &str) -> &str {
+    let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+    word.strip_suffix("'s")
+        .or_else(|| word.strip_suffix("’s"))
+        .unwrap_or(word)
+}
+
+/// Runs of capitalized words in a sentence, excluding the first word of the sentence, the word
+/// "I" and lexicon terms. Consecutive capitalized words are joined, as in "New York Times".
+pub fn extract_entities(sentence: &str, lexicons: &[Lexicon]) -> Vec<String> {
+    let mut entities: Vec<String> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut flush = |current: &mut Vec<&str>| {
+        if !current.is_empty() {
+            let entity = current.join(" ");
+            if !entities.contains(&entity) {
+                entities.push(entity);
+            }
+            current.clear();
+        }
+    };
+    let mut is_first_word = true;
+    for raw in sentence.split_whitespace() {
+        let word = trim_word(raw);
+        if word.is_empty() {
+            // Punctuation such as dashes or quotes on its own
+            flush(&mut current);
+            continue;
+        }
+        let is_capitalized = word.chars().next().is_some_and(char::is_uppercase);
+        let lowercase = word.to_lowercase();
+        let is_excluded =
+            is_first_word || word == "I" || lexicons.iter().any(|l| l.contains(&lowercase));
+        is_first_word = false;
+        if is_capitalized && !is_excluded {
+            current.push(word);
+        } else {
+            flush(&mut current);
+        }
+        // Punctuation after a word ends the entity. Periods are not checked, as they are also
+        // used in abbreviations such as "Mr."
+        if raw.ends_with([',', ';', ':', ')', '"', '”']) {
+            flush(&mut current);
+        }
+    }
+    flush(&mut current);
+    entities
+}
+
+/// Extract the sentences of the record's content that contain lexicon terms, or `None` if there
+/// are no such sentences
+pub fn extract_contexts(record: &Record, lexicons: &[Lexicon]) -> Option<ArticleContext> {
+    let content = &record.content;
+    let mut sentences = Vec::new();
+    let mut sentence_index = 0;
+    let mut start = 0;
+    let mut word_index = 0;
+    let mut matches = Vec::new();
+    tokenize(content, |token| match token {
+        Token::Word(word) => {
+            for lexicon in lexicons.iter().filter(|l| l.contains(word)) {
+                matches.push(TermMatch {
+                    lexicon: lexicon.name.clone(),
+                    term: word.to_string(),
+                    word_index,
+                });
+            }
+            word_index += 1;
+        }
+        Token::SentenceEnd(end) => {
+            if !matches.is_empty() {
+                let raw = &content[start..end];
+                let text = raw.trim();
+                let sentence_start = start + (raw.len() - raw.trim_start().len());
+                sentences.push(SentenceContext {
+                    sentence_index,
+                    start: sentence_start,
+                    end: sentence_start + text.len(),
+                    text: text.to_string(),
+                    matches: std::mem::take(&mut matches),
+                    entities: extract_entities(text, lexicons),
+                });
+            }
+            sentence_index += 1;
+            start = end;
+            word_index = 0;
+        }
+    });
+    if sentences.is_empty() {
+        None
+    } else {
+        Some(ArticleContext {
+            id: record.id,
+            sentences,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexicon::default_lexicons;
+
+    #[test]
+    fn test_extract_entities() {
+        let sentence =
+            "He told The New York Times that Mr. Trump’s aide, Sean Spicer, and I agreed.";
+        assert_eq!(
+            extract_entities(sentence, &default_lexicons()),
+            vec!["The New York Times", "Mr Trump", "Sean Spicer"]
+        );
+    }
+
+    #[test]
+    fn test_extract_contexts() {
+        let record = Record {
+            id: 7,
+            publication: "Guardian".to_string(),
+            author: "A".to_string(),
+            date: "2017-01-01".to_string(),
+            content: "The vote was close. Senator Warren said she would object. \
+                      Mr. Smith thanked her and his staff."
+                .to_string(),
+        };
+        let context = extract_contexts(&record, &default_lexicons()).unwrap();
+        assert_eq!(context.id, 7);
+        assert_eq!(context.sentences.len(), 2);
+
+        let first = &context.sentences[0];
+        assert_eq!(first.sentence_index, 1);
+        assert_eq!(first.text, "Senator Warren said she would object.");
+        assert_eq!(&record.content[first.start..first.end], first.text);
+        assert_eq!(
+            first.matches,
+            vec![TermMatch {
+                lexicon: "female_pronouns".to_string(),
+                term: "she".to_string(),
+                word_index: 3,
+            }]
+        );
+        assert_eq!(first.entities, vec!["Warren"]);
+
+        let second = &context.sentences[1];
+        assert_eq!(second.text, "Mr. Smith thanked her and his staff.");
+        let terms: Vec<&str> = second.matches.iter().map(|m| m.term.as_str()).collect();
+        assert_eq!(terms, vec!["her", "his"]);
+        assert_eq!(second.entities, vec!["Smith"]);
+    }
+
+    #[test]
+    fn test_extract_contexts_without_matches() {
+        let record = Record {
+            id: 1,
+            publication: "Guardian".to_string(),
+            author: "A".to_string(),
+            date: "2017-01-01".to_string(),
+            content: "Nothing to see here.".to_string(),
+        };
+        assert_eq!(extract_contexts(&record, &default_lexicons()), None);
+    }
+}
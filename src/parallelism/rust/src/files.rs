@@ -0,0 +1,134 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+const PROCESSED_SUFFIX: &str = "_processed";
+
+/// Whether the path is an input articles CSV, rather than an output written by this program
+fn is_input_csv(path: &Path) -> bool {
+    let is_csv = path.extension().is_some_and(|ext| ext == "csv");
+    let is_processed = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| stem.ends_with(PROCESSED_SUFFIX));
+    path.is_file() && is_csv && !is_processed
+}
+
+/// Expand a list of files, directories and glob patterns into a sorted list of input CSV files.
+/// Directories are expanded to the CSV files they contain (non-recursively).
+pub fn resolve_inputs(inputs: &[String]) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            let entries =
+                fs::read_dir(path).with_context(|| format!("Unable to read directory {path:?}"))?;
+            for entry in entries {
+                let entry_path = entry?.path();
+                if is_input_csv(&entry_path) {
+                    paths.push(entry_path);
+                }
+            }
+        } else if path.is_file() {
+            paths.push(path.to_path_buf());
+        } else {
+            let matches =
+                glob::glob(input).with_context(|| format!("Invalid glob pattern {input:?}"))?;
+            let mut found = false;
+            for entry in matches {
+                let entry_path = entry?;
+                if is_input_csv(&entry_path) {
+                    paths.push(entry_path);
+                    found = true;
+                }
+            }
+            if !found {
+                bail!("No input CSV files found for {input:?}");
+            }
+        }
+    }
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// The path `<stem><suffix>` for an input file, in `dir` if provided, otherwise next to the input
+fn derived_path(input_path: &Path, dir: Option<&Path>, suffix: &str) -> Result<PathBuf> {
+    let Some(stem) = input_path.file_stem() else {
+        bail!("Invalid input file name {input_path:?}");
+    };
+    let mut file_name = stem.to_os_string();
+    file_name.push(suffix);
+    let dir = match dir {
+        Some(dir) => dir,
+        None => input_path.parent().unwrap_or(Path::new("")),
+    };
+    Ok(dir.join(file_name))
+}
+
+/// The output path for an input file: `<stem>_processed.csv`, in `output_dir` if provided,
+/// otherwise next to the input file
+pub fn output_path(input_path: &Path, output_dir: Option<&Path>) -> Result<PathBuf> {
+    derived_path(input_path, output_dir, &format!("{PROCESSED_SUFFIX}.csv"))
+}
+
+/// The path of the sentence contexts for an input file: `<stem>_contexts.jsonl` in `dir`
+pub fn contexts_path(input_path: &Path, dir: &Path) -> Result<PathBuf> {
+    derived_path(input_path, Some(dir), "_contexts.jsonl")
+}
+
+/// The path of the checkpoint for an output file: `<output stem>.checkpoint.json`, next to it
+pub fn checkpoint_path(output_path: &Path) -> Result<PathBuf> {
+    derived_path(output_path, None, ".checkpoint.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn articles() -> Vec<PathBuf> {
+        ["articles1.csv", "articles2.csv", "articles3.csv"]
+            .iter()
+            .map(|name| Path::new("../data").join(name))
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_inputs_directory() {
+        let paths = resolve_inputs(&["../data".to_string()]).unwrap();
+        assert_eq!(paths, articles());
+    }
+
+    #[test]
+    fn test_resolve_inputs_glob_and_files() {
+        let inputs = [
+            "../data/articles*.csv".to_string(),
+            "../data/articles2.csv".to_string(),
+        ];
+        let paths = resolve_inputs(&inputs).unwrap();
+        assert_eq!(paths, articles());
+        assert!(resolve_inputs(&["../data/missing*.csv".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_output_path() {
+        let input = Path::new("../data/articles.v1.csv");
+        assert_eq!(
+            output_path(input, None).unwrap(),
+            Path::new("../data/articles.v1_processed.csv")
+        );
+        assert_eq!(
+            output_path(input, Some(Path::new("out"))).unwrap(),
+            Path::new("out/articles.v1_processed.csv")
+        );
+        assert_eq!(
+            contexts_path(input, Path::new("out")).unwrap(),
+            Path::new("out/articles.v1_contexts.jsonl")
+        );
+        assert_eq!(
+            checkpoint_path(Path::new("out/articles.v1_processed.csv")).unwrap(),
+            Path::new("out/articles.v1_processed.checkpoint.json")
+        );
+    }
+}
@@ -0,0 +1,371 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+
+use crate::lexicon::Lexicon;
+use crate::pipeline::{process_csv, Skip};
+use crate::RecordProcessed;
+
+/// Progress of a file being processed, saved periodically so that an interrupted job can be
+/// resumed. The output files are only guaranteed to be valid up to the saved lengths: anything
+/// written after the last checkpoint is discarded on resume.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    // Number of input records whose results are in the output
+    pub records_done: usize,
+    // Id of the last record done, checked on resume to detect an input that changed in place
+    pub last_id: Option<u32>,
+    // Size of the input file, used to detect that it changed since the checkpoint
+    pub input_len: u64,
+    pub header: Vec<String>,
+    pub output_len: u64,
+    pub contexts_len: Option<u64>,
+    pub complete: bool,
+}
+
+impl Checkpoint {
+    /// Load a checkpoint, or return `None` if there is none
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Unable to read checkpoint {path:?}"))?;
+        let checkpoint = serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid checkpoint {path:?}"))?;
+        Ok(Some(checkpoint))
+    }
+
+    /// Save the checkpoint to a temporary file which is then renamed, so that a crash while
+    /// saving never leaves a truncated checkpoint behind
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut tmp_path = path.as_os_str().to_os_string();
+        tmp_path.push(".tmp");
+        let mut file = File::create(&tmp_path)?;
+        serde_json::to_writer(&mut file, self)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    }
+}
+
+/// Number of records in a file's output, and how many of them were resumed from a checkpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JobStats {
+    pub num_records: usize,
+    pub num_resumed: usize,
+}
+
+/// Processing of one input file, with periodic checkpoints
+#[derive(Debug, Clone)]
+pub struct FileJob<'a> {
+    pub input_path: &'a Path,
+    pub output_path: PathBuf,
+    // Where to write the sentence contexts, if they are extracted
+    pub contexts_path: Option<PathBuf>,
+    pub checkpoint_path: PathBuf,
+    pub lexicons: &'a [Lexicon],
+    pub chunk_size: usize,
+    // Minimum time between two checkpoints
+    pub checkpoint_interval: Duration,
+    // Resume from the checkpoint, if there is one, instead of starting over
+    pub resume: bool,
+    // Advanced by the number of bytes read from the input
+    pub progress: ProgressBar,
+}
+
+/// Truncate a file to `len` and open it for appending, or create it if `len` is `None`
+fn open_output(path: &Path, len: Option<u64>) -> Result<File> {
+    match len {
+        Some(len) => {
+            let mut file = OpenOptions::new()
+                .write(true)
+                .open(path)
+                .with_context(|| format!("Unable to open {path:?} to resume it"))?;
+            if file.metadata()?.len() < len {
+                bail!("{path:?} is shorter than its checkpoint, unable to resume");
+            }
+            file.set_len(len)?;
+            file.seek(SeekFrom::End(0))?;
+            Ok(file)
+        }
+        None => File::create(path).with_context(|| format!("Unable to create {path:?}")),
+    }
+}
+
+impl FileJob<'_> {
+    /// Load the checkpoint to resume from, checking that it matches the current input and options
+    fn load_checkpoint(&self, input_len: u64, header: &[String]) -> Result<Option<Checkpoint>> {
+        if !self.resume {
+            return Ok(None);
+        }
+        let Some(checkpoint) = Checkpoint::load(&self.checkpoint_path)? else {
+            return Ok(None);
+        };
+        let input_path = self.input_path;
+        if checkpoint.input_len != input_len {
+            bail!("Unable to resume {input_path:?}: the file changed since it was checkpointed");
+        }
+        if checkpoint.header != header {
+            bail!("Unable to resume {input_path:?}: it was checkpointed with different lexicons");
+        }
+        if checkpoint.contexts_len.is_some() != self.contexts_path.is_some() {
+            bail!(
+                "Unable to resume {input_path:?}: it was checkpointed {} sentence contexts",
+                if checkpoint.contexts_len.is_some() {
+                    "with"
+                } else {
+                    "without"
+                }
+            );
+        }
+        Ok(Some(checkpoint))
+    }
+
+    /// Read back the output written before the checkpoint, calling `on_chunk` on its records
+    fn replay<F>(&self, checkpoint: &Checkpoint, on_chunk: &mut F) -> Result<()>
+    where
+        F: FnMut(&[RecordProcessed]) -> io::Result<()>,
+    {
+        let output_path = &self.output_path;
+        let file = File::open(output_path)
+            .with_context(|| format!("Unable to read {output_path:?} to resume it"))?;
+        let mut rdr = csv::Reader::from_reader(io::Read::take(file, checkpoint.output_len));
+        if rdr.headers()? != checkpoint.header.as_slice() {
+            bail!("Unable to resume {output_path:?}: unexpected header");
+        }
+        let chunk_size = self.chunk_size.max(1);
+        let mut chunk = Vec::with_capacity(chunk_size);
+        let mut num_records = 0;
+        for row in rdr.records() {
+            let record = RecordProcessed::from_record(&row?, self.lexicons.len())
+                .with_context(|| format!("Invalid record in {output_path:?}"))?;
+            chunk.push(record);
+            if chunk.len() == chunk_size {
+                num_records += chunk.len();
+                on_chunk(&chunk)?;
+                chunk.clear();
+            }
+        }
+        if !chunk.is_empty() {
+            num_records += chunk.len();
+            on_chunk(&chunk)?;
+        }
+        if num_records != checkpoint.records_done {
+            bail!(
+                "Unable to resume {output_path:?}: expected {} records, found {num_records}",
+                checkpoint.records_done
+            );
+        }
+        Ok(())
+    }
+
+    /// Process the input file, saving a checkpoint at most every `checkpoint_interval` and once
+    /// the file is complete. When resuming, `on_chunk` is first called on the records already in
+    /// the output, so that it sees every record of the file exactly once.
+    pub fn run<F>(&self, mut on_chunk: F) -> Result<JobStats>
+    where
+        F: FnMut(&[RecordProcessed]) -> io::Result<()>,
+    {
+        let input_path = self.input_path;
+        let input_len = fs::metadata(input_path)
+            .with_context(|| format!("Unable to read from CSV {input_path:?}"))?
+            .len();
+        let header = RecordProcessed::header(self.lexicons);
+        let checkpoint = self.load_checkpoint(input_len, &header)?;
+        if let Some(checkpoint) = &checkpoint {
+            self.replay(checkpoint, &mut on_chunk)?;
+            if checkpoint.complete {
+                self.progress.inc(input_len);
+                return Ok(JobStats {
+                    num_records: checkpoint.records_done,
+                    num_resumed: checkpoint.records_done,
+                });
+            }
+        }
+
+        let reader = File::open(input_path)
+            .with_context(|| format!("Unable to read from CSV {input_path:?}"))?;
+        let output = open_output(&self.output_path, checkpoint.as_ref().map(|c| c.output_len))?;
+        let contexts = match &self.contexts_path {
+            Some(path) => Some(open_output(
+                path,
+                checkpoint.as_ref().and_then(|c| c.contexts_len),
+            )?),
+            None => None,
+        };
+        let mut contexts_writer = contexts.as_ref().map(BufWriter::new);
+        let num_resumed = checkpoint.as_ref().map_or(0, |c| c.records_done);
+        let skip = Skip {
+            records: num_resumed,
+            last_id: checkpoint.as_ref().and_then(|c| c.last_id),
+        };
+        let mut state = checkpoint.unwrap_or(Checkpoint {
+            records_done: 0,
+            last_id: None,
+            input_len,
+            header,
+            output_len: 0,
+            contexts_len: contexts.as_ref().map(|_| 0),
+            complete: false,
+        });
+        let mut last_saved = Instant::now();
+        let save = |state: &mut Checkpoint| -> io::Result<()> {
+            // Both writers were flushed, so the file sizes include every record done
+            state.output_len = output.metadata()?.len();
+            if let Some(contexts) = &contexts {
+                state.contexts_len = Some(contexts.metadata()?.len());
+            }
+            state.save(&self.checkpoint_path)
+        };
+        process_csv(
+            self.progress.wrap_read(reader),
+            &output,
+            contexts_writer.as_mut().map(|w| w as &mut dyn Write),
+            self.lexicons,
+            self.chunk_size,
+            skip,
+            |chunk| {
+                state.records_done += chunk.len();
+                state.last_id = chunk.last().map(|r| r.id).or(state.last_id);
+                if last_saved.elapsed() >= self.checkpoint_interval {
+                    save(&mut state)?;
+                    last_saved = Instant::now();
+                }
+                on_chunk(chunk)
+            },
+        )
+        .with_context(|| format!("Unable to process {input_path:?}"))?;
+        state.complete = true;
+        save(&mut state)
+            .with_context(|| format!("Unable to save checkpoint {:?}", self.checkpoint_path))?;
+        Ok(JobStats {
+            num_records: state.records_done,
+            num_resumed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexicon::default_lexicons;
+
+    fn job<'a>(dir: &Path, input_path: &'a Path, lexicons: &'a [Lexicon]) -> FileJob<'a> {
+        FileJob {
+            input_path,
+            output_path: dir.join("articles1_processed.csv"),
+            contexts_path: Some(dir.join("articles1_contexts.jsonl")),
+            checkpoint_path: dir.join("articles1_processed.checkpoint.json"),
+            lexicons,
+            chunk_size: 3,
+            checkpoint_interval: Duration::ZERO,
+            resume: true,
+            progress: ProgressBar::hidden(),
+        }
+    }
+
+    #[test]
+    fn test_resume_after_interruption() {
+        let dir = std::env::temp_dir().join(format!("parallelism_job_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input_path = Path::new("../data/articles1.csv");
+        let lexicons = default_lexicons();
+        let job = job(&dir, input_path, &lexicons);
+
+        // A complete run, without resuming
+        let expected = FileJob {
+            output_path: dir.join("expected_processed.csv"),
+            contexts_path: Some(dir.join("expected_contexts.jsonl")),
+            checkpoint_path: dir.join("expected_processed.checkpoint.json"),
+            resume: false,
+            ..job.clone()
+        };
+        let stats = expected.run(|_| Ok(())).unwrap();
+        assert_eq!(stats.num_resumed, 0);
+
+        // Interrupted while processing the second chunk
+        let mut num_chunks = 0;
+        let result = job.run(|_| {
+            num_chunks += 1;
+            if num_chunks == 2 {
+                return Err(io::Error::other("interrupted"));
+            }
+            Ok(())
+        });
+        assert!(result.is_err());
+        let checkpoint = Checkpoint::load(&job.checkpoint_path).unwrap().unwrap();
+        assert!(!checkpoint.complete);
+        // Output written after the checkpoint is discarded on resume
+        let mut output = OpenOptions::new()
+            .append(true)
+            .open(&job.output_path)
+            .unwrap();
+        writeln!(output, "999,Partial,Row").unwrap();
+
+        let mut ids = Vec::new();
+        let stats = job
+            .run(|chunk| {
+                ids.extend(chunk.iter().map(|r| r.id));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(stats.num_resumed, checkpoint.records_done);
+        assert_eq!(stats.num_records, 11);
+        assert_eq!(ids.len(), 11);
+        assert_eq!(
+            fs::read(&job.output_path).unwrap(),
+            fs::read(&expected.output_path).unwrap()
+        );
+        assert_eq!(
+            fs::read(job.contexts_path.as_ref().unwrap()).unwrap(),
+            fs::read(expected.contexts_path.as_ref().unwrap()).unwrap()
+        );
+
+        // A complete job is not processed again
+        let stats = job.run(|_| Ok(())).unwrap();
+        assert_eq!(stats.num_resumed, stats.num_records);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_resume_rejects_different_last_id() {
+        let dir = std::env::temp_dir().join(format!("parallelism_id_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input_path = Path::new("../data/articles1.csv");
+        let lexicons = default_lexicons();
+        let job = job(&dir, input_path, &lexicons);
+        let mut num_chunks = 0;
+        let result = job.run(|_| {
+            num_chunks += 1;
+            if num_chunks == 2 {
+                return Err(io::Error::other("interrupted"));
+            }
+            Ok(())
+        });
+        assert!(result.is_err());
+        // As if the records of the input had been rewritten in place, with the same size
+        let mut checkpoint = Checkpoint::load(&job.checkpoint_path).unwrap().unwrap();
+        checkpoint.last_id = checkpoint.last_id.map(|id| id + 1);
+        checkpoint.save(&job.checkpoint_path).unwrap();
+        let err = job.run(|_| Ok(())).unwrap_err();
+        assert!(format!("{err:#}").contains("to have id"), "{err:#}");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_resume_rejects_different_lexicons() {
+        let dir = std::env::temp_dir().join(format!("parallelism_lex_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input_path = Path::new("../data/articles1.csv");
+        let lexicons = default_lexicons();
+        job(&dir, input_path, &lexicons).run(|_| Ok(())).unwrap();
+        let other = vec![Lexicon::new("honorifics", &["mr", "mrs"])];
+        assert!(job(&dir, input_path, &other).run(|_| Ok(())).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::text::is_apostrophe;
+
+/// A named list of terms whose occurrences are counted in each article
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Lexicon {
+    pub name: String,
+    pub terms: HashSet<String>,
+}
+
+impl Lexicon {
+    pub fn new(name: &str, terms: &[&str]) -> Self {
+        Lexicon {
+            name: name.to_string(),
+            terms: terms.iter().map(|t| t.to_lowercase()).collect(),
+        }
+    }
+
+    /// Name of the output column that holds the counts for this lexicon
+    pub fn column_name(&self) -> String {
+        format!("num_{}", self.name)
+    }
+
+    pub fn contains(&self, token: &str) -> bool {
+        self.terms.contains(token)
+    }
+}
+
+/// The lexicons used when no lexicon file is provided: male and female pronouns
+pub fn default_lexicons() -> Vec<Lexicon> {
+    vec![
+        Lexicon::new("male_pronouns", &["he", "him", "his"]),
+        Lexicon::new("female_pronouns", &["she", "her", "hers"]),
+    ]
+}
+
+/// Load lexicons from a JSON file containing a list of `{"name": ..., "terms": [...]}` objects.
+/// The order of the lexicons in the file determines the order of the output columns.
+pub fn load_lexicons(path: &Path) -> Result<Vec<Lexicon>> {
+    if !path.exists() {
+        bail!("Lexicon file {:?} not found", path);
+    }
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Unable to read lexicons {path:?}"))?;
+    let lexicons: Vec<Lexicon> =
+        serde_json::from_str(&contents).with_context(|| format!("Invalid lexicons {path:?}"))?;
+    validate(lexicons).with_context(|| format!("Invalid lexicons {path:?}"))
+}
+
+fn validate(lexicons: Vec<Lexicon>) -> Result<Vec<Lexicon>> {
+    if lexicons.is_empty() {
+        bail!("At least one lexicon must be defined");
+    }
+    let mut names = HashSet::new();
+    let mut result = Vec::with_capacity(lexicons.len());
+    for lexicon in lexicons {
+        if lexicon.name.is_empty()
+            || !lexicon
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            bail!(
+                "Invalid lexicon name {:?}: only letters, digits and underscores are allowed",
+                lexicon.name
+            );
+        }
+        if !names.insert(lexicon.name.clone()) {
+            bail!("Duplicate lexicon name {:?}", lexicon.name);
+        }
+        if lexicon.terms.is_empty() {
+            bail!("Lexicon {:?} has no terms", lexicon.name);
+        }
+        // Tokens never contain spaces or apostrophes, so such terms would never be counted
+        let mut terms: Vec<&String> = lexicon.terms.iter().collect();
+        terms.sort();
+        if let Some(term) = terms
+            .into_iter()
+            .find(|t| t.is_empty() || t.chars().any(|c| c.is_whitespace() || is_apostrophe(c)))
+        {
+            bail!(
+                "Invalid term {term:?} in lexicon {:?}: terms must be single words, without \
+                 spaces or apostrophes",
+                lexicon.name
+            );
+        }
+        // Terms are matched against cleaned (lowercased) tokens
+        let terms = lexicon.terms.iter().map(|t| t.to_lowercase()).collect();
+        result.push(Lexicon {
+            name: lexicon.name,
+            terms,
+        });
+    }
+    Ok(result)
+}
+
+/// Increment the count of every lexicon that contains `token`
+pub fn count_token(token: &str, lexicons: &[Lexicon], counts: &mut [usize]) {
+    for (count, lexicon) in counts.iter_mut().zip(lexicons) {
+        if lexicon.contains(token) {
+            *count += 1;
+        }
+    }
+}
+
+/// Count the occurrences of each lexicon's terms in the tokens, in lexicon order
+pub fn count_terms(tokens: &[&str], lexicons: &[Lexicon]) -> Vec<usize> {
+    let mut counts = vec![0; lexicons.len()];
+    for token in tokens {
+        count_token(token, lexicons, &mut counts);
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_lexicons() {
+        let path = Path::new("../data/lexicons.json");
+        let lexicons = load_lexicons(path).unwrap();
+        let names: Vec<&str> = lexicons.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "male_pronouns",
+                "female_pronouns",
+                "neutral_pronouns",
+                "honorifics"
+            ]
+        );
+        assert!(lexicons[2].contains("they"));
+        // The errors name the file
+        let err = load_lexicons(Path::new("../data/articles1.csv")).unwrap_err();
+        assert!(format!("{err:#}").starts_with("Invalid lexicons \"../data/articles1.csv\""));
+    }
+
+    #[test]
+    fn test_validate_lexicons() {
+        let duplicate = vec![Lexicon::new("a", &["x"]), Lexicon::new("a", &["y"])];
+        assert!(validate(duplicate).is_err());
+        assert!(validate(vec![Lexicon::new("a", &[])]).is_err());
+        assert!(validate(vec![Lexicon::new("not valid", &["x"])]).is_err());
+        assert!(validate(vec![]).is_err());
+        for term in ["mr smith", "o'neill", "he’s", ""] {
+            let err = validate(vec![Lexicon::new("names", &["x", term])]).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "Invalid term {term:?} in lexicon \"names\": terms must be single words, \
+                     without spaces or apostrophes"
+                )
+            );
+        }
+        let lexicons = validate(vec![Lexicon::new("titles", &["Mr", "MRS"])]).unwrap();
+        assert!(lexicons[0].contains("mr") && lexicons[0].contains("mrs"));
+    }
+
+    #[test]
+    fn test_count_terms() {
+        let lexicons = vec![
+            Lexicon::new("neutral", &["they", "them"]),
+            Lexicon::new("honorifics", &["mr", "mrs", "ms"]),
+        ];
+        let tokens = vec![
+            "they", "told", "mr", "smith", "and", "ms", "jones", "about", "them",
+        ];
+        assert_eq!(count_terms(&tokens, &lexicons), vec![2, 2]);
+    }
+}
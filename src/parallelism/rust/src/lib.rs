@@ -0,0 +1,131 @@
+pub mod analysis;
+pub mod context;
+pub mod files;
+pub mod job;
+pub mod lexicon;
+pub mod metrics;
+pub mod pipeline;
+pub mod summary;
+pub mod text;
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+use crate::lexicon::{count_token, Lexicon};
+use crate::text::for_each_token;
+
+#[derive(Deserialize, Debug)]
+pub struct Record {
+    pub id: u32,
+    pub publication: String,
+    pub author: String,
+    pub date: String,
+    pub content: String,
+}
+
+#[derive(Debug)]
+pub struct RecordProcessed {
+    pub id: u32,
+    pub publication: String,
+    pub author: String,
+    pub date: String,
+    // Term counts, one per lexicon and in the same order as the lexicons
+    pub counts: Vec<usize>,
+}
+
+impl RecordProcessed {
+    pub fn header(lexicons: &[Lexicon]) -> Vec<String> {
+        let mut header: Vec<String> = ["id", "publication", "author", "date"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        header.extend(lexicons.iter().map(Lexicon::column_name));
+        header
+    }
+
+    pub fn to_record(&self) -> Vec<String> {
+        let mut record = vec![
+            self.id.to_string(),
+            self.publication.clone(),
+            self.author.clone(),
+            self.date.clone(),
+        ];
+        record.extend(self.counts.iter().map(|c| c.to_string()));
+        record
+    }
+
+    /// Parse a row written by `to_record`, with `num_lexicons` counts
+    pub fn from_record(record: &csv::StringRecord, num_lexicons: usize) -> Result<Self> {
+        if record.len() != 4 + num_lexicons {
+            bail!(
+                "Expected {} fields, found {}",
+                4 + num_lexicons,
+                record.len()
+            );
+        }
+        Ok(RecordProcessed {
+            id: record[0].parse()?,
+            publication: record[1].to_string(),
+            author: record[2].to_string(),
+            date: record[3].to_string(),
+            counts: record
+                .iter()
+                .skip(4)
+                .map(|c| c.parse())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+pub fn process_record(record: &Record, lexicons: &[Lexicon]) -> RecordProcessed {
+    let mut counts = vec![0; lexicons.len()];
+    for_each_token(&record.content, |token| {
+        count_token(token, lexicons, &mut counts)
+    });
+    RecordProcessed {
+        id: record.id,
+        publication: record.publication.to_string(),
+        author: record.author.to_string(),
+        date: record.date.to_string(),
+        counts,
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexicon::{count_terms, default_lexicons};
+    use crate::text::clean_text;
+
+    #[test]
+    fn test_process_record() {
+        let record = Record {
+            id: 1,
+            publication: "The New York Times".to_string(),
+            author: "Rob Williamson".to_string(),
+            date: "2016-12-31".to_string(),
+            content: "She's swum the fastest she's ever done in her life.".to_string(),
+        };
+        let result = process_record(&record, &default_lexicons());
+        assert_eq!(result.counts, vec![0, 3]);
+        assert_eq!(
+            RecordProcessed::header(&default_lexicons())[4..],
+            ["num_male_pronouns", "num_female_pronouns"]
+        );
+        let row = csv::StringRecord::from(result.to_record());
+        let parsed = RecordProcessed::from_record(&row, 2).unwrap();
+        assert_eq!(parsed.to_record(), result.to_record());
+        assert!(RecordProcessed::from_record(&row, 3).is_err());
+    }
+
+    #[test]
+    fn test_count_gendered_pronouns() {
+        let text = "He's he’ll he’d HE she's she'll She’d SHE random text here";
+        let clean_text = clean_text(text);
+        let tokens: Vec<&str> = clean_text.split_whitespace().collect();
+        let counts = count_terms(&tokens, &default_lexicons());
+        assert_eq!(counts, vec![4, 4]);
+    }
+}
@@ -1,147 +1,266 @@
-use rayon::prelude::*;
-use regex::{Captures, Regex};
-use std::fs;
+mod cli;
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
-use serde::{Deserialize, Serialize};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use cpu_time::ProcessTime;
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
 
-#[allow(dead_code)]
-#[derive(Deserialize, Debug)]
-struct Record {
-    id: u32,
-    publication: String,
-    author: String,
-    date: String,
-    content: String,
-}
+use parallelism::analysis::{analyze_csv, write_frequencies, write_top_terms, TermStats};
+use parallelism::files::{checkpoint_path, contexts_path, output_path, resolve_inputs};
+use parallelism::job::FileJob;
+use parallelism::lexicon::{default_lexicons, load_lexicons, Lexicon};
+use parallelism::metrics::{FileMetrics, Metrics};
+use parallelism::summary::Summaries;
 
-#[allow(dead_code)]
-#[derive(Deserialize, Serialize, Debug)]
-struct RecordProcessed {
-    id: u32,
-    publication: String,
-    author: String,
-    date: String,
-    num_male_pronouns: usize,
-    num_female_pronouns: usize,
-}
+use crate::cli::Args;
 
-fn load_csv(path: &Path) -> Result<Vec<Record>, csv::Error> {
-    let contents = fs::read_to_string(path).expect("Unable to read from CSV");
-    let mut rdr = csv::Reader::from_reader(contents.as_bytes());
-    let data: Vec<Record> = rdr.deserialize().collect::<Result<_, _>>().unwrap();
-    Ok(data)
+struct FileSummary {
+    input_path: PathBuf,
+    output_path: PathBuf,
+    checkpoint_path: PathBuf,
+    num_records: usize,
+    // Records already processed before the job was resumed
+    num_resumed: usize,
+    elapsed: Duration,
+    summaries: Summaries,
 }
 
-fn clean_text(text: &str) -> String {
-    let pattern1 = Regex::new(r"([’'])(s|d|ll)").unwrap();
-    // Replace pattern with text
-    let matched = pattern1.replace_all(text, |capture: &Captures| match &capture[2] {
-        "s" => " is",
-        "d" => " had",
-        "ll" => " will",
-        _ => "<unk>",
-    });
-    // Remove non-alphabetic characters
-    let pattern2 = Regex::new(r"[^a-zA-Z\s]").unwrap();
-    let clean_text = pattern2.replace_all(&matched, "");
-    let result: String = clean_text.to_lowercase();
-    result
+/// Progress of all the files, shown as a bar over the bytes read from the inputs (which gives the
+/// ETA) along with the number of records processed and the throughput
+struct Progress {
+    bar: ProgressBar,
+    num_records: AtomicUsize,
+    start: Instant,
 }
 
-fn count_gendered_pronouns(tokens: Vec<&str>) -> (usize, usize) {
-    let num_male_pronouns = tokens
-        .par_iter()
-        .filter(|&x| *x == "he" || *x == "him" || *x == "his")
-        .count();
-    let num_female_pronouns = tokens
-        .par_iter()
-        .filter(|&x| *x == "she" || *x == "her" || *x == "hers")
-        .count();
-    (num_male_pronouns, num_female_pronouns)
-}
+impl Progress {
+    fn new(paths: &[PathBuf], hidden: bool) -> Result<Self> {
+        let bar = if hidden {
+            ProgressBar::hidden()
+        } else {
+            let total_bytes = paths
+                .iter()
+                .map(|path| fs::metadata(path).map(|m| m.len()))
+                .sum::<io::Result<u64>>()?;
+            let style = ProgressStyle::with_template(
+                "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {percent}% (ETA {eta}) {msg}",
+            )?
+            .progress_chars("=> ");
+            ProgressBar::new(total_bytes).with_style(style)
+        };
+        Ok(Progress {
+            bar,
+            num_records: AtomicUsize::new(0),
+            start: Instant::now(),
+        })
+    }
 
-fn process_record(record: &Record) -> RecordProcessed {
-    let text = &record.content;
-    let result: String = clean_text(text);
-    let tokens: Vec<&str> = result.split_whitespace().collect();
-    let (n_m, n_f) = count_gendered_pronouns(tokens);
-    RecordProcessed {
-        id: record.id,
-        publication: record.publication.to_string(),
-        author: record.author.to_string(),
-        date: record.date.to_string(),
-        num_male_pronouns: n_m,
-        num_female_pronouns: n_f,
+    fn add(&self, num_records: usize) {
+        let total = self.num_records.fetch_add(num_records, Ordering::Relaxed) + num_records;
+        let records_per_sec = total as f64 / self.start.elapsed().as_secs_f64();
+        self.bar
+            .set_message(format!("{total} records, {records_per_sec:.0} records/s"));
     }
 }
 
-fn run(input_path: &PathBuf) {
-    let data = load_csv(Path::new(input_path)).unwrap();
-    let records = data.par_iter().map(process_record).collect::<Vec<_>>();
-    let output_path = input_path
-        .clone()
-        .into_os_string()
-        .into_string()
-        .expect("Unable to convert path");
-    let output_path = output_path.replace(".csv", "_processed.csv");
-    let mut wtr = csv::Writer::from_path(Path::new(&output_path)).unwrap();
-    _ = records.iter().map(|x| wtr.serialize(x)).collect::<Vec<_>>();
-    println!("Results for {input_path:?} to written to {output_path:?}");
+fn run(
+    input_path: &Path,
+    args: &Args,
+    lexicons: &[Lexicon],
+    progress: &Progress,
+) -> Result<FileSummary> {
+    let start = Instant::now();
+    let output_path = output_path(input_path, args.output_dir.as_deref())?;
+    let contexts_path = match &args.context_dir {
+        Some(dir) => Some(contexts_path(input_path, dir)?),
+        None => None,
+    };
+    let job = FileJob {
+        input_path,
+        checkpoint_path: checkpoint_path(&output_path)?,
+        output_path,
+        contexts_path,
+        lexicons,
+        chunk_size: args.chunk_size,
+        checkpoint_interval: Duration::from_secs(args.checkpoint_interval),
+        resume: args.resume,
+        progress: progress.bar.clone(),
+    };
+    let mut summaries = Summaries::new(lexicons.len());
+    let stats = job.run(|chunk| {
+        chunk.iter().for_each(|record| summaries.add(record));
+        progress.add(chunk.len());
+        Ok(())
+    })?;
+    Ok(FileSummary {
+        input_path: input_path.to_path_buf(),
+        output_path: job.output_path,
+        checkpoint_path: job.checkpoint_path,
+        num_records: stats.num_records,
+        num_resumed: stats.num_resumed,
+        elapsed: start.elapsed(),
+        summaries,
+    })
 }
 
-fn main() {
-    let paths = [
-        PathBuf::from("../data/articles1.csv"),
-        PathBuf::from("../data/articles2.csv"),
-        PathBuf::from("../data/articles3.csv"),
-    ];
-    for path in paths {
-        run(&path);
+fn write_summaries(summaries: &Summaries, dir: &Path, lexicons: &[Lexicon]) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Unable to create directory {dir:?}"))?;
+    for summary in summaries.iter() {
+        let path = dir.join(format!("summary_by_{}.csv", summary.group_by().name()));
+        let writer =
+            File::create(&path).with_context(|| format!("Unable to create summary {path:?}"))?;
+        summary.write(writer, lexicons)?;
+        println!(
+            "Summary by {} written to {path:?}",
+            summary.group_by().name()
+        );
     }
+    Ok(())
 }
 
-// Tests
+fn create_file(path: &Path) -> Result<File> {
+    File::create(path).with_context(|| format!("Unable to create {path:?}"))
+}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Compute word frequencies, bigram frequencies and the top terms per publication over all the
+/// input files, and write them to `dir`
+fn analyze(paths: &[PathBuf], dir: &Path, args: &Args) -> Result<()> {
+    let start = Instant::now();
+    fs::create_dir_all(dir).with_context(|| format!("Unable to create directory {dir:?}"))?;
+    let stats = paths
+        .par_iter()
+        .map(|path| {
+            let reader =
+                File::open(path).with_context(|| format!("Unable to read from CSV {path:?}"))?;
+            analyze_csv(reader, args.chunk_size)
+                .with_context(|| format!("Unable to process {path:?}"))
+        })
+        .try_reduce(TermStats::default, |a, b| Ok(a.merge(b)))?;
 
-    #[test]
-    fn test_load_csv() {
-        let path = Path::new("../data/articles1.csv");
-        let data = load_csv(path).unwrap();
-        assert!(!data.is_empty());
-    }
+    let unigrams_path = dir.join("unigrams.csv");
+    write_frequencies(
+        create_file(&unigrams_path)?,
+        &stats.unigrams,
+        args.min_count,
+    )?;
+    let bigrams_path = dir.join("bigrams.csv");
+    write_frequencies(create_file(&bigrams_path)?, &stats.bigrams, args.min_count)?;
+    let top_terms_path = dir.join("top_terms_by_publication.csv");
+    write_top_terms(create_file(&top_terms_path)?, &stats, args.top_n)?;
+    println!(
+        "Analyzed {} records from {} files in {:.3} sec: {} unigrams and {} bigrams written to {dir:?}",
+        stats.num_documents,
+        paths.len(),
+        start.elapsed().as_secs_f64(),
+        stats.unigrams.len(),
+        stats.bigrams.len(),
+    );
+    Ok(())
+}
 
-    #[test]
-    fn test_clean_text() {
-        let text = "He's he’s he’ll she'll he’d She'd HE SHE";
-        let result = clean_text(text);
-        assert_eq!(result, "he is he is he will she will he had she had he she");
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()?;
+    }
+    let lexicons = match &args.lexicons {
+        Some(path) => load_lexicons(path)?,
+        None => default_lexicons(),
+    };
+    let paths = resolve_inputs(&args.inputs)?;
+    if let Some(dir) = &args.analysis_dir {
+        return analyze(&paths, dir, &args);
+    }
+    let mut output_paths = HashSet::new();
+    for path in paths.iter() {
+        let output_path = output_path(path, args.output_dir.as_deref())?;
+        if !output_paths.insert(output_path.clone()) {
+            bail!("More than one input file would be written to {output_path:?}");
+        }
+    }
+    for dir in [&args.output_dir, &args.context_dir].into_iter().flatten() {
+        fs::create_dir_all(dir).with_context(|| format!("Unable to create directory {dir:?}"))?;
     }
 
-    #[test]
-    fn test_process_record() {
-        let record = Record {
-            id: 1,
-            publication: "The New York Times".to_string(),
-            author: "Rob Williamson".to_string(),
-            date: "2016-12-31".to_string(),
-            content: "She's swum the fastest she's ever done in her life.".to_string(),
+    let start = Instant::now();
+    let cpu_start = ProcessTime::now();
+    let progress = Progress::new(&paths, args.no_progress)?;
+    // Files are processed concurrently, and each file's chunks share the same thread pool
+    let file_summaries = paths
+        .par_iter()
+        .map(|path| run(path, &args, &lexicons, &progress))
+        .collect::<Result<Vec<_>>>();
+    progress.bar.finish_and_clear();
+    let file_summaries = file_summaries?;
+    let wall_time = start.elapsed();
+    let cpu_time = cpu_start.elapsed();
+    for summary in file_summaries.iter() {
+        let resumed = match summary.num_resumed {
+            0 => String::new(),
+            n => format!(" (resumed after {n} records)"),
         };
-        let result = process_record(&record);
-        assert!(result.num_male_pronouns == 0);
-        assert!(result.num_female_pronouns == 3);
+        println!(
+            "Results for {} records in {:?} written to {:?} in {:.3} sec{resumed}",
+            summary.num_records,
+            summary.input_path,
+            summary.output_path,
+            summary.elapsed.as_secs_f64()
+        );
+        // The checkpoints are only needed until every file is complete
+        fs::remove_file(&summary.checkpoint_path).with_context(|| {
+            format!("Unable to remove checkpoint {:?}", summary.checkpoint_path)
+        })?;
     }
-
-    #[test]
-    fn test_count_gendered_pronouns() {
-        let text = "He's he’ll he’d HE she's she'll She’d SHE random text here";
-        let clean_text = clean_text(text);
-        let tokens = clean_text.split_whitespace().collect();
-        let (n_m, n_f) = count_gendered_pronouns(tokens);
-        assert_eq!(n_m, 4);
-        assert_eq!(n_f, 4);
+    let file_metrics = file_summaries
+        .iter()
+        .map(|s| {
+            FileMetrics::new(
+                s.input_path.clone(),
+                s.num_records,
+                s.num_resumed,
+                s.elapsed,
+            )
+        })
+        .collect();
+    let metrics = Metrics::new(
+        file_metrics,
+        rayon::current_num_threads(),
+        wall_time,
+        cpu_time,
+    );
+    if let Some(dir) = &args.summary_dir {
+        // Combine the summaries of all the files into one
+        let summaries = file_summaries
+            .into_iter()
+            .map(|s| s.summaries)
+            .fold(Summaries::new(lexicons.len()), Summaries::merge);
+        write_summaries(&summaries, dir, &lexicons)?;
+    }
+    println!(
+        "Processed {} records from {} files in {:.3} sec using {} threads",
+        metrics.num_records, metrics.num_files, metrics.wall_time_secs, metrics.num_threads
+    );
+    println!(
+        "CPU time {:.3} sec ({:.0}% utilization), {:.0} records/s, {:.0} records/s per thread",
+        metrics.cpu_time_secs,
+        metrics.cpu_utilization * 100.0,
+        metrics.records_per_sec,
+        metrics.records_per_sec_per_thread
+    );
+    if let Some(path) = &args.metrics {
+        serde_json::to_writer_pretty(create_file(path)?, &metrics)
+            .with_context(|| format!("Unable to write metrics to {path:?}"))?;
+        println!("Metrics written to {path:?}");
     }
+    Ok(())
 }
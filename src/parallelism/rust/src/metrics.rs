@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Serialize;
+
+fn per_sec(count: usize, secs: f64) -> f64 {
+    if secs > 0.0 {
+        count as f64 / secs
+    } else {
+        0.0
+    }
+}
+
+/// Throughput of one input file
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FileMetrics {
+    pub input_path: PathBuf,
+    pub num_records: usize,
+    // Records processed by a previous run, which are not included in the throughput
+    pub num_resumed: usize,
+    pub elapsed_secs: f64,
+    pub records_per_sec: f64,
+}
+
+impl FileMetrics {
+    pub fn new(
+        input_path: PathBuf,
+        num_records: usize,
+        num_resumed: usize,
+        elapsed: Duration,
+    ) -> Self {
+        let elapsed_secs = elapsed.as_secs_f64();
+        FileMetrics {
+            input_path,
+            num_records,
+            num_resumed,
+            elapsed_secs,
+            records_per_sec: per_sec(num_records - num_resumed, elapsed_secs),
+        }
+    }
+}
+
+/// Throughput and resource usage of a whole run, written as JSON for benchmarking
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Metrics {
+    pub num_files: usize,
+    pub num_records: usize,
+    pub num_resumed: usize,
+    pub num_threads: usize,
+    pub wall_time_secs: f64,
+    // CPU time of the process, over all threads
+    pub cpu_time_secs: f64,
+    pub records_per_sec: f64,
+    pub records_per_sec_per_thread: f64,
+    // Share of the threads' available time spent on the CPU, between 0 and 1
+    pub cpu_utilization: f64,
+    pub files: Vec<FileMetrics>,
+}
+
+impl Metrics {
+    pub fn new(
+        files: Vec<FileMetrics>,
+        num_threads: usize,
+        wall_time: Duration,
+        cpu_time: Duration,
+    ) -> Self {
+        let wall_time_secs = wall_time.as_secs_f64();
+        let cpu_time_secs = cpu_time.as_secs_f64();
+        let num_records = files.iter().map(|f| f.num_records).sum();
+        let num_resumed = files.iter().map(|f| f.num_resumed).sum();
+        let records_per_sec = per_sec(num_records - num_resumed, wall_time_secs);
+        let available_secs = wall_time_secs * num_threads as f64;
+        Metrics {
+            num_files: files.len(),
+            num_records,
+            num_resumed,
+            num_threads,
+            wall_time_secs,
+            cpu_time_secs,
+            records_per_sec,
+            records_per_sec_per_thread: records_per_sec / num_threads.max(1) as f64,
+            cpu_utilization: if available_secs > 0.0 {
+                cpu_time_secs / available_secs
+            } else {
+                0.0
+            },
+            files,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics() {
+        let files = vec![
+            FileMetrics::new("a.csv".into(), 300, 0, Duration::from_secs(3)),
+            FileMetrics::new("b.csv".into(), 500, 100, Duration::from_secs(2)),
+        ];
+        assert_eq!(files[1].records_per_sec, 200.0);
+        let metrics = Metrics::new(files, 4, Duration::from_secs(2), Duration::from_secs(6));
+        assert_eq!(metrics.num_records, 800);
+        // Resumed records were not processed in this run
+        assert_eq!(metrics.records_per_sec, 350.0);
+        assert_eq!(metrics.records_per_sec_per_thread, 87.5);
+        assert_eq!(metrics.cpu_utilization, 0.75);
+    }
+}
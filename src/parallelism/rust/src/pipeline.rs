@@ -0,0 +1,198 @@
+use std::io::{self, Write};
+
+use rayon::prelude::*;
+
+use crate::context::extract_contexts;
+use crate::lexicon::Lexicon;
+use crate::{process_record, Record, RecordProcessed};
+
+/// Number of records held in memory per chunk. While one chunk is processed on the rayon pool,
+/// the next one is read from the input, so at most two chunks are in memory at any time.
+pub const DEFAULT_CHUNK_SIZE: usize = 1000;
+
+fn read_chunk<I>(records: &mut I, chunk_size: usize) -> Result<Vec<Record>, csv::Error>
+where
+    I: Iterator<Item = Result<Record, csv::Error>>,
+{
+    records.take(chunk_size).collect()
+}
+
+/// Read records from `records` in chunks of `chunk_size`, calling `process` on each chunk
+/// (typically in parallel on the rayon pool) while the next chunk is read, and then `consume` on
+/// its result, in input order. Returns the number of records read.
+pub fn process_chunks<I, T, P, C>(
+    mut records: I,
+    chunk_size: usize,
+    process: P,
+    mut consume: C,
+) -> Result<usize, csv::Error>
+where
+    I: Iterator<Item = Result<Record, csv::Error>> + Send,
+    T: Send,
+    P: Fn(&[Record]) -> T + Sync,
+    C: FnMut(T) -> Result<(), csv::Error>,
+{
+    let chunk_size = chunk_size.max(1);
+    let mut num_records = 0;
+    let mut chunk = read_chunk(&mut records, chunk_size)?;
+    while !chunk.is_empty() {
+        // Read the next chunk while the current one is being processed
+        let (result, next_chunk) =
+            rayon::join(|| process(&chunk), || read_chunk(&mut records, chunk_size));
+        consume(result)?;
+        num_records += chunk.len();
+        chunk = next_chunk?;
+    }
+    Ok(num_records)
+}
+
+/// Records already processed at the start of the input, when resuming from a partial output
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Skip {
+    pub records: usize,
+    // Id that the last skipped record must have, to detect an input that changed since then
+    pub last_id: Option<u32>,
+}
+
+/// Stream records from `reader` in chunks, process each chunk in parallel and write the results
+/// to `writer` in input order. If a `contexts` writer is provided, the sentences containing
+/// lexicon terms are also extracted and written to it as JSONL, one line per article.
+///
+/// The first `skip.records` records are skipped without being processed, and the header is then
+/// not written, so that an interrupted job can be resumed by appending to its partial output. Both
+/// writers are flushed after each chunk, after which `on_chunk` is called on the chunk's
+/// processed records. Returns the number of records processed.
+pub fn process_csv<R, W, F>(
+    reader: R,
+    writer: W,
+    mut contexts: Option<&mut dyn io::Write>,
+    lexicons: &[Lexicon],
+    chunk_size: usize,
+    skip: Skip,
+    mut on_chunk: F,
+) -> Result<usize, csv::Error>
+where
+    R: io::Read + Send,
+    W: io::Write,
+    F: FnMut(&[RecordProcessed]) -> io::Result<()>,
+{
+    let mut records = csv::Reader::from_reader(reader).into_deserialize::<Record>();
+    let Skip {
+        records: skip,
+        last_id,
+    } = skip;
+    let mut last_skipped = None;
+    for num_skipped in 0..skip {
+        match records.next().transpose()? {
+            Some(record) => last_skipped = Some(record.id),
+            None => {
+                let message = format!("Expected to skip {skip} records, found {num_skipped}");
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, message).into());
+            }
+        }
+    }
+    if let (Some(expected), Some(id)) = (last_id, last_skipped) {
+        if id != expected {
+            let message = format!("Expected record {skip} to have id {expected}, found {id}");
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message).into());
+        }
+    }
+    let mut wtr = csv::Writer::from_writer(writer);
+    if skip == 0 {
+        wtr.write_record(RecordProcessed::header(lexicons))?;
+    }
+    let extract = contexts.is_some();
+    let num_records = process_chunks(
+        records,
+        chunk_size,
+        |chunk| {
+            chunk
+                .par_iter()
+                .map(|record| {
+                    let context = extract
+                        .then(|| extract_contexts(record, lexicons))
+                        .flatten();
+                    (process_record(record, lexicons), context)
+                })
+                .unzip::<_, _, Vec<_>, Vec<_>>()
+        },
+        |(processed, chunk_contexts)| {
+            // `unzip` on an indexed parallel iterator preserves the input order
+            for record in processed.iter() {
+                wtr.write_record(record.to_record())?;
+            }
+            wtr.flush()?;
+            if let Some(writer) = contexts.as_mut() {
+                for context in chunk_contexts.iter().flatten() {
+                    serde_json::to_writer(&mut *writer, context).map_err(io::Error::from)?;
+                    writeln!(writer)?;
+                }
+                writer.flush()?;
+            }
+            on_chunk(&processed)?;
+            Ok(())
+        },
+    )?;
+    wtr.flush()?;
+    Ok(num_records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexicon::default_lexicons;
+    use std::fs::File;
+
+    #[test]
+    fn test_process_csv_preserves_order() {
+        let input = "_,id,title,publication,author,date,year,month,url,content\n\
+            0,3,t,Pub,A,2017-01-01,,,,He said\n\
+            1,1,t,Pub,B,2017-01-02,,,,She said she was here\n\
+            2,2,t,Pub,C,2017-01-03,,,,Nothing to see\n";
+        let mut output = Vec::new();
+        let n = process_csv(
+            input.as_bytes(),
+            &mut output,
+            None,
+            &default_lexicons(),
+            2,
+            Skip::default(),
+            |_| Ok(()),
+        )
+        .unwrap();
+        assert_eq!(n, 3);
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "id,publication,author,date,num_male_pronouns,num_female_pronouns",
+                "3,Pub,A,2017-01-01,1,0",
+                "1,Pub,B,2017-01-02,0,2",
+                "2,Pub,C,2017-01-03,0,0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_process_csv_chunk_size_independent() {
+        let lexicons = default_lexicons();
+        let mut outputs = Vec::new();
+        for chunk_size in [1, 3, DEFAULT_CHUNK_SIZE] {
+            let file = File::open("../data/articles1.csv").unwrap();
+            let mut output = Vec::new();
+            process_csv(
+                file,
+                &mut output,
+                None,
+                &lexicons,
+                chunk_size,
+                Skip::default(),
+                |_| Ok(()),
+            )
+            .unwrap();
+            outputs.push(output);
+        }
+        assert!(outputs.windows(2).all(|w| w[0] == w[1]));
+    }
+}
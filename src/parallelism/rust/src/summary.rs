@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::io;
+
+use chrono::NaiveDate;
+
+use crate::lexicon::Lexicon;
+use crate::RecordProcessed;
+
+const UNKNOWN: &str = "unknown";
+
+/// The field that records are grouped by in a summary
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    Publication,
+    Author,
+    Month,
+}
+
+impl GroupBy {
+    pub const ALL: [GroupBy; 3] = [GroupBy::Publication, GroupBy::Author, GroupBy::Month];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GroupBy::Publication => "publication",
+            GroupBy::Author => "author",
+            GroupBy::Month => "month",
+        }
+    }
+
+    fn key(&self, record: &RecordProcessed) -> String {
+        let key = match self {
+            GroupBy::Publication => record.publication.trim().to_string(),
+            GroupBy::Author => record.author.trim().to_string(),
+            GroupBy::Month => parse_month(&record.date).unwrap_or_default(),
+        };
+        if key.is_empty() {
+            UNKNOWN.to_string()
+        } else {
+            key
+        }
+    }
+}
+
+/// Parse the month of a `YYYY-MM-DD` date, formatted as `YYYY-MM`
+pub fn parse_month(date: &str) -> Option<String> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .ok()
+        .map(|d| d.format("%Y-%m").to_string())
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct GroupStats {
+    num_articles: usize,
+    counts: Vec<usize>,
+}
+
+/// Term counts per lexicon, rolled up over all the records in each group
+#[derive(Debug, Clone)]
+pub struct Summary {
+    group_by: GroupBy,
+    num_lexicons: usize,
+    groups: HashMap<String, GroupStats>,
+}
+
+impl Summary {
+    pub fn new(group_by: GroupBy, num_lexicons: usize) -> Self {
+        Summary {
+            group_by,
+            num_lexicons,
+            groups: HashMap::new(),
+        }
+    }
+
+    pub fn group_by(&self) -> GroupBy {
+        self.group_by
+    }
+
+    pub fn add(&mut self, record: &RecordProcessed) {
+        let stats = self
+            .groups
+            .entry(self.group_by.key(record))
+            .or_insert_with(|| GroupStats {
+                num_articles: 0,
+                counts: vec![0; self.num_lexicons],
+            });
+        stats.num_articles += 1;
+        for (total, count) in stats.counts.iter_mut().zip(&record.counts) {
+            *total += count;
+        }
+    }
+
+    /// Combine the groups of another summary of the same kind into this one
+    pub fn merge(&mut self, other: Summary) {
+        for (key, other_stats) in other.groups {
+            let stats = self.groups.entry(key).or_insert_with(|| GroupStats {
+                num_articles: 0,
+                counts: vec![0; self.num_lexicons],
+            });
+            stats.num_articles += other_stats.num_articles;
+            for (total, count) in stats.counts.iter_mut().zip(&other_stats.counts) {
+                *total += count;
+            }
+        }
+    }
+
+    /// Write one row per group, sorted by the group key. Each lexicon's ratio is its share of
+    /// the total term count across all lexicons in the group (empty if the group has no terms).
+    pub fn write<W: io::Write>(&self, writer: W, lexicons: &[Lexicon]) -> Result<(), csv::Error> {
+        let mut wtr = csv::Writer::from_writer(writer);
+        let mut header = vec![self.group_by.name().to_string(), "num_articles".to_string()];
+        header.extend(lexicons.iter().map(Lexicon::column_name));
+        header.extend(lexicons.iter().map(|l| format!("ratio_{}", l.name)));
+        wtr.write_record(&header)?;
+
+        let mut keys: Vec<&String> = self.groups.keys().collect();
+        keys.sort();
+        for key in keys {
+            let stats = &self.groups[key];
+            let total: usize = stats.counts.iter().sum();
+            let mut row = vec![key.clone(), stats.num_articles.to_string()];
+            row.extend(stats.counts.iter().map(|c| c.to_string()));
+            row.extend(stats.counts.iter().map(|&c| match total {
+                0 => String::new(),
+                _ => format!("{:.4}", c as f64 / total as f64),
+            }));
+            wtr.write_record(&row)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+/// One summary for each way of grouping the records
+#[derive(Debug, Clone)]
+pub struct Summaries(Vec<Summary>);
+
+impl Summaries {
+    pub fn new(num_lexicons: usize) -> Self {
+        Summaries(
+            GroupBy::ALL
+                .iter()
+                .map(|&group_by| Summary::new(group_by, num_lexicons))
+                .collect(),
+        )
+    }
+
+    pub fn add(&mut self, record: &RecordProcessed) {
+        for summary in self.0.iter_mut() {
+            summary.add(record);
+        }
+    }
+
+    pub fn merge(mut self, other: Summaries) -> Self {
+        for (summary, other) in self.0.iter_mut().zip(other.0) {
+            summary.merge(other);
+        }
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Summary> {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexicon::default_lexicons;
+
+    fn record(publication: &str, author: &str, date: &str, counts: Vec<usize>) -> RecordProcessed {
+        RecordProcessed {
+            id: 1,
+            publication: publication.to_string(),
+            author: author.to_string(),
+            date: date.to_string(),
+            counts,
+        }
+    }
+
+    fn write_to_string(summary: &Summary) -> String {
+        let mut output = Vec::new();
+        summary.write(&mut output, &default_lexicons()).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_parse_month() {
+        assert_eq!(parse_month("2016-12-31"), Some("2016-12".to_string()));
+        assert_eq!(parse_month(" 2017-01-05 "), Some("2017-01".to_string()));
+        assert_eq!(parse_month("31/12/2016"), None);
+        assert_eq!(parse_month(""), None);
+    }
+
+    #[test]
+    fn test_summary_by_month() {
+        let mut summary = Summary::new(GroupBy::Month, 2);
+        summary.add(&record("NYT", "A", "2016-12-31", vec![3, 1]));
+        summary.add(&record("CNN", "B", "2016-12-01", vec![1, 3]));
+        summary.add(&record("CNN", "B", "2017-01-02", vec![0, 0]));
+        summary.add(&record("CNN", "B", "not a date", vec![1, 0]));
+        let expected = "month,num_articles,num_male_pronouns,num_female_pronouns,\
+                        ratio_male_pronouns,ratio_female_pronouns\n\
+                        2016-12,2,4,4,0.5000,0.5000\n\
+                        2017-01,1,0,0,,\n\
+                        unknown,1,1,0,1.0000,0.0000\n";
+        assert_eq!(write_to_string(&summary), expected);
+    }
+
+    #[test]
+    fn test_summaries_merge() {
+        let mut left = Summaries::new(2);
+        left.add(&record("NYT", "A", "2016-12-31", vec![3, 1]));
+        let mut right = Summaries::new(2);
+        right.add(&record("NYT", "", "2017-01-31", vec![1, 1]));
+        let merged = left.merge(right);
+        let outputs: Vec<String> = merged.iter().map(write_to_string).collect();
+        assert!(outputs[0].ends_with("NYT,2,4,2,0.6667,0.3333\n"));
+        assert!(outputs[1].contains("A,1,3,1,0.7500,0.2500\n"));
+        assert!(outputs[1].contains("unknown,1,1,1,0.5000,0.5000\n"));
+        assert!(outputs[2].contains("2017-01,1,1,1,0.5000,0.5000\n"));
+    }
+}
@@ -0,0 +1,388 @@
+use unicode_normalization::char::is_combining_mark;
+
+/// A token produced by `tokenize`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Token<'a> {
+    /// A cleaned, lowercased word
+    Word(&'a str),
+    /// The end of a sentence, with the byte offset in the text just after its final punctuation
+    SentenceEnd(usize),
+}
+
+/// Options for `clean_text_with`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanOptions {
+    /// Separate sentences with a newline instead of a space
+    pub preserve_sentences: bool,
+}
+
+// Words after which `'s` is a contraction of "is" rather than a possessive
+const IS_CONTRACTION_BASES: [&str; 12] = [
+    "he", "she", "it", "that", "this", "what", "where", "who", "there", "here", "how", "when",
+];
+
+// Words ending with a period that do not end a sentence
+const ABBREVIATIONS: [&str; 28] = [
+    "mr", "mrs", "ms", "dr", "prof", "st", "jr", "sr", "gen", "gov", "sen", "rep", "lt", "col",
+    "vs", "etc", "jan", "feb", "mar", "apr", "jun", "jul", "aug", "sep", "sept", "oct", "nov",
+    "dec",
+];
+
+/// Whether a one-letter word followed by a period is an initial, as in "Donald J. Trump": an
+/// uppercase letter followed by a capitalized word. "I" usually ends a sentence ("so did I."), so it
+/// is only an initial if followed by another one, as in "I. M. Pei".
+fn is_initial(word: &str, rest: &str) -> bool {
+    let mut letters = word.chars();
+    let (Some(letter), None) = (letters.next(), letters.next()) else {
+        return false;
+    };
+    let mut next = rest.trim_start().chars();
+    let next_capitalized = next.next().is_some_and(char::is_uppercase);
+    letter.is_uppercase() && next_capitalized && (letter != 'I' || next.next() == Some('.'))
+}
+
+pub(crate) fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '’'
+}
+
+fn is_word_char(c: char) -> bool {
+    if c.is_ascii() {
+        c.is_ascii_alphabetic()
+    } else {
+        c.is_alphabetic() || is_combining_mark(c)
+    }
+}
+
+fn is_sentence_terminal(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '…' | '。' | '！' | '？')
+}
+
+#[derive(Debug, PartialEq)]
+enum Contraction {
+    Expand(&'static str),
+    Possessive,
+    Unknown,
+}
+
+/// The expansion of a contraction suffix (the lowercased letters after the apostrophe), if any.
+/// `'s` is either "is" or a possessive, and `n't` also changes the word before it, so both are
+/// handled separately.
+fn expand_contraction(suffix: &str) -> Option<&'static str> {
+    match suffix {
+        "re" => Some("are"),
+        "ve" => Some("have"),
+        "m" => Some("am"),
+        "ll" => Some("will"),
+        "d" => Some("had"),
+        _ => None,
+    }
+}
+
+/// The word before `n't`, given the word with its final `n` removed: "do" for "don't",
+/// "can" for "can't", "will" for "won't"
+fn negation_base(stem: &str) -> &str {
+    match stem {
+        "ca" => "can",
+        "wo" => "will",
+        "sha" => "shall",
+        "ai" => "is",
+        _ => stem,
+    }
+}
+
+/// Split `text` into cleaned, lowercased words in a single pass, calling `f` on each word and at
+/// the end of each sentence.
+///
+/// * Letters in any script (including accented and combining characters) are kept, all other
+///   characters separate words, except periods within abbreviations such as "U.S."
+/// * Contractions are expanded: "he's" to "he is", "don't" to "do not", "they're" to "they are",
+///   "we've" to "we have", "I'm" to "i am", "she'll" to "she will" and "he'd" to "he had"
+/// * Possessives are removed: "Trump's" and "students'" become "trump" and "students"
+/// * Other apostrophes split words ("l'été" becomes "l été"), unless followed by a capital letter
+///   as in "O'Neill"
+///
+/// A single buffer is reused for every word, so no intermediate strings are built.
+pub fn tokenize<F: FnMut(Token)>(text: &str, mut f: F) {
+    let mut token = String::with_capacity(32);
+    let mut suffix = String::with_capacity(8);
+    // Offset of the current token in the text
+    let mut token_start = 0;
+    // Whether the current token contains periods, as in "U.S."
+    let mut token_has_period = false;
+    // Whether any words were emitted since the last sentence end
+    let mut in_sentence = false;
+
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        if token.is_empty() {
+            token_start = i;
+        }
+        if c.is_ascii_alphabetic() {
+            token.push(c.to_ascii_lowercase());
+            continue;
+        }
+        if is_word_char(c) {
+            token.extend(c.to_lowercase());
+            continue;
+        }
+        if c == '.' && !token.is_empty() && next.is_some_and(char::is_alphabetic) {
+            // A period within an abbreviation such as "U.S.": keep the word together
+            token_has_period = true;
+            continue;
+        }
+        if is_apostrophe(c) && !token.is_empty() && next.is_some_and(is_word_char) {
+            // Collect the letters after the apostrophe without consuming them
+            suffix.clear();
+            let mut lookahead = chars.clone();
+            let mut capitalized = false;
+            while let Some(&(_, s)) = lookahead.peek() {
+                if !is_word_char(s) {
+                    break;
+                }
+                capitalized |= suffix.is_empty() && s.is_uppercase();
+                suffix.extend(s.to_lowercase());
+                lookahead.next();
+            }
+            let contraction = if suffix == "s" {
+                if token == "let" {
+                    Contraction::Expand("us")
+                } else if IS_CONTRACTION_BASES.contains(&token.as_str()) {
+                    Contraction::Expand("is")
+                } else {
+                    Contraction::Possessive
+                }
+            } else if suffix == "t" && token.ends_with('n') && token.len() > 1 {
+                token.pop();
+                let base = negation_base(&token).to_string();
+                token.clear();
+                token.push_str(&base);
+                Contraction::Expand("not")
+            } else {
+                match expand_contraction(&suffix) {
+                    Some(expansion) => Contraction::Expand(expansion),
+                    None => Contraction::Unknown,
+                }
+            };
+            // Names such as O'Neill are kept as a single word
+            if contraction == Contraction::Unknown && capitalized {
+                continue;
+            }
+            f(Token::Word(&token));
+            token.clear();
+            token_has_period = false;
+            in_sentence = true;
+            match contraction {
+                Contraction::Expand(expansion) => {
+                    f(Token::Word(expansion));
+                    chars = lookahead;
+                }
+                // The suffix is skipped
+                Contraction::Possessive => chars = lookahead,
+                // The suffix becomes the next word
+                Contraction::Unknown => {}
+            }
+            continue;
+        }
+
+        // Any other character ends the current word
+        let is_abbreviation = token_has_period
+            || ABBREVIATIONS.contains(&token.as_str())
+            || (c == '.' && !token.is_empty() && is_initial(&text[token_start..i], &text[i + 1..]));
+        if !token.is_empty() {
+            f(Token::Word(&token));
+            token.clear();
+            token_has_period = false;
+            in_sentence = true;
+        }
+        let ends_sentence = is_sentence_terminal(c)
+            && !(c == '.' && is_abbreviation)
+            && !next.is_some_and(char::is_alphanumeric);
+        if ends_sentence && in_sentence {
+            // Closing quotes and brackets right after the punctuation belong to the sentence
+            let mut end = i + c.len_utf8();
+            while let Some(&(j, q)) = chars.peek() {
+                if !matches!(q, '"' | '”' | '\'' | '’' | ')' | ']' | '»') {
+                    break;
+                }
+                end = j + q.len_utf8();
+                chars.next();
+            }
+            f(Token::SentenceEnd(end));
+            in_sentence = false;
+        }
+    }
+    if !token.is_empty() {
+        f(Token::Word(&token));
+        in_sentence = true;
+    }
+    if in_sentence {
+        f(Token::SentenceEnd(text.len()));
+    }
+}
+
+/// Call `f` on each cleaned word of `text`, in a single pass (see `tokenize`)
+pub fn for_each_token<F: FnMut(&str)>(text: &str, mut f: F) {
+    tokenize(text, |token| {
+        if let Token::Word(word) = token {
+            f(word)
+        }
+    });
+}
+
+/// Clean the text into lowercased words separated by spaces (see `tokenize`)
+pub fn clean_text(text: &str) -> String {
+    clean_text_with(text, CleanOptions::default())
+}
+
+/// Clean the text into lowercased words separated by spaces, optionally with one sentence per line
+pub fn clean_text_with(text: &str, options: CleanOptions) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut separator = "";
+    tokenize(text, |token| match token {
+        Token::Word(word) => {
+            result.push_str(separator);
+            result.push_str(word);
+            separator = " ";
+        }
+        Token::SentenceEnd(_) if options.preserve_sentences && !result.is_empty() => {
+            separator = "\n";
+        }
+        Token::SentenceEnd(_) => {}
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str) -> Vec<String> {
+        let mut result = Vec::new();
+        for_each_token(text, |token| result.push(token.to_string()));
+        result
+    }
+
+    #[test]
+    fn test_clean_text() {
+        let text = "He's he’s he’ll she'll he’d She'd HE SHE";
+        let result = clean_text(text);
+        assert_eq!(result, "he is he is he will she will he had she had he she");
+    }
+
+    #[test]
+    fn test_contractions() {
+        let text = "They're sure we've won't, I'm told. Don't, can't, let's go";
+        assert_eq!(
+            clean_text(text),
+            "they are sure we have will not i am told do not can not let us go"
+        );
+    }
+
+    #[test]
+    fn test_possessives() {
+        let text = "Trump’s policy, the students' books and O'Neill's 'quoted' words";
+        assert_eq!(
+            clean_text(text),
+            "trump policy the students books and oneill quoted words"
+        );
+    }
+
+    #[test]
+    fn test_for_each_token() {
+        let text = "He's he’ll she'l — the U.S. in 2016, well-known\tHERS";
+        assert_eq!(
+            tokens(text),
+            vec!["he", "is", "he", "will", "she", "l", "the", "us", "in", "well", "known", "hers"]
+        );
+    }
+
+    #[test]
+    fn test_clean_text_non_english() {
+        assert_eq!(
+            clean_text("L'été à Montréal, c'est déjà l'hiver!"),
+            "l été à montréal c est déjà l hiver"
+        );
+        assert_eq!(
+            clean_text("Die Straße in München ist schön."),
+            "die straße in münchen ist schön"
+        );
+        assert_eq!(
+            clean_text("Он сказал, что она придёт"),
+            "он сказал что она придёт"
+        );
+        // Decomposed accents (a letter followed by a combining mark) are kept
+        assert_eq!(
+            clean_text("Cafe\u{301} Zoe\u{308}"),
+            "cafe\u{301} zoe\u{308}"
+        );
+    }
+
+    #[test]
+    fn test_preserve_sentences() {
+        let options = CleanOptions {
+            preserve_sentences: true,
+        };
+        let text =
+            "Mr. Smith went to the U.S. in 2016. Did he stay? \"Yes!\" he said… It's 3.5 miles";
+        assert_eq!(
+            clean_text_with(text, options),
+            "mr smith went to the us in\ndid he stay\nyes\nhe said\nit is miles"
+        );
+    }
+
+    #[test]
+    fn test_sentence_end_offsets() {
+        let text = "She left. He stayed!  They";
+        let mut offsets = Vec::new();
+        tokenize(text, |token| {
+            if let Token::SentenceEnd(i) = token {
+                offsets.push(i)
+            }
+        });
+        assert_eq!(offsets, vec![9, 20, 26]);
+    }
+
+    #[test]
+    fn test_sentence_end_quotes_and_months() {
+        let text = "She left. “He stayed!”  They met on Jan. 5 (in Paris.) Then";
+        let mut offsets = Vec::new();
+        tokenize(text, |token| {
+            if let Token::SentenceEnd(i) = token {
+                offsets.push(i)
+            }
+        });
+        // Closing quotes and brackets are included in the sentence, and month abbreviations
+        // don't end it
+        assert_eq!(offsets, vec![9, 26, 58, text.len()]);
+    }
+
+    #[test]
+    fn test_sentence_end_initials() {
+        let text = "So did I. Then Donald J. Trump and I. M. Pei left. Ask for plan a. Then go";
+        let mut offsets = Vec::new();
+        tokenize(text, |token| {
+            if let Token::SentenceEnd(i) = token {
+                offsets.push(i)
+            }
+        });
+        // "I" and lowercase letters end sentences, but initials don't
+        let end = |s: &str| text.find(s).unwrap() + s.len();
+        assert_eq!(
+            offsets,
+            vec![end("did I."), end("left."), end("plan a."), text.len()]
+        );
+    }
+
+    #[test]
+    fn test_for_each_token_matches_clean_text() {
+        let text = "SEOUL, South Korea  —   North Korea’s leader, Kim   said on Sunday that his \
+                    country was making final preparations, \"a bold statement,\" before the \
+                    inauguration of Donald J. Trump.";
+        let expected = "seoul south korea north korea leader kim said on sunday that his country \
+                        was making final preparations a bold statement before the inauguration \
+                        of donald j trump";
+        assert_eq!(clean_text(text), expected);
+        assert_eq!(tokens(text), expected.split(' ').collect::<Vec<_>>());
+    }
+}
@@ -1,3 +1,23 @@
-from pyo3_mock_data._pyo3_mock_data import generate_mock_persons
+from pyo3_mock_data._pyo3_mock_data import (
+    CitiesFileError,
+    CitiesParseError,
+    MockDataError,
+    MockPersonIterator,
+    OutputWriteError,
+    Person,
+    PersonBatch,
+    generate_mock_persons,
+    generate_mock_persons_arrow,
+)
 
-__all__ = ["generate_mock_persons"]
+__all__ = [
+    "CitiesFileError",
+    "CitiesParseError",
+    "MockDataError",
+    "MockPersonIterator",
+    "OutputWriteError",
+    "Person",
+    "PersonBatch",
+    "generate_mock_persons",
+    "generate_mock_persons_arrow",
+]
@@ -1,7 +1,90 @@
 from __future__ import annotations
 
+from collections.abc import Iterator
 from pathlib import Path
+from typing import Any, Literal, overload
 
+class MockDataError(Exception):
+    """Base class of the errors of pyo3_mock_data.
+
+    The message of an error includes the message of its cause, e.g. the underlying I/O error.
+    """
+
+class CitiesFileError(MockDataError):
+    """The cities file can't be opened or read, e.g. because it doesn't exist."""
+
+    path: str
+
+class CitiesParseError(MockDataError):
+    """The cities file isn't a valid cities file, e.g. because a column is missing.
+
+    `row` is the line of the file with the error, or `None` if the error isn't about a row.
+    """
+
+    path: str
+    row: int | None
+
+class OutputWriteError(MockDataError):
+    """An output file can't be created or written."""
+
+    path: str
+
+class Person:
+    @property
+    def id(self) -> int: ...
+    @property
+    def name(self) -> str: ...
+    @property
+    def age(self) -> int: ...
+    @property
+    def is_married(self) -> bool: ...
+    @property
+    def city(self) -> str: ...
+    @property
+    def state(self) -> str: ...
+    @property
+    def country(self) -> str: ...
+    @property
+    def lat(self) -> float: ...
+    @property
+    def lng(self) -> float: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class PersonBatch:
+    """Profiles as an Arrow record batch, exported through the Arrow PyCapsule interface."""
+
+    @property
+    def num_rows(self) -> int: ...
+    @property
+    def column_names(self) -> list[str]: ...
+    def __len__(self) -> int: ...
+    def __arrow_c_schema__(self) -> object: ...
+    def __arrow_c_array__(self, requested_schema: object | None = None) -> tuple[object, object]: ...
+    def __arrow_c_stream__(self, requested_schema: object | None = None) -> object: ...
+
+class MockPersonIterator:
+    """Yields persons lazily, one at a time, or in lists of `batch_size` persons if given."""
+
+    def __init__(
+        self, filename: Path | str, limit: int | None = None, *, batch_size: int | None = None
+    ) -> None: ...
+    def __iter__(self) -> Iterator[Person | list[Person]]: ...
+    def __next__(self) -> Person | list[Person]: ...
+
+@overload
 def generate_mock_persons(
-    filename: Path | str, limit: int = 10, *, output_filename: Path | str | None = None
+    filename: Path | str,
+    limit: int = 10,
+    *,
+    output_filename: Path | str | None = None,
+    as_list: Literal[False] = False,
 ) -> None: ...
+@overload
+def generate_mock_persons(
+    filename: Path | str,
+    limit: int = 10,
+    *,
+    output_filename: Path | str | None = None,
+    as_list: Literal[True],
+) -> list[Person]: ...
+def generate_mock_persons_arrow(filename: Path | str, limit: int = 10) -> PersonBatch: ...
@@ -1,30 +1,52 @@
-use std::{
-    fs,
-    io::Write,
-    path::{Path, PathBuf},
-};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 
-use fake::{faker::name::raw::Name, locales::EN, Fake, Rng};
+use arrow_array::ffi::{to_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow_array::ffi_stream::FFI_ArrowArrayStream;
+use arrow_array::{Array, RecordBatch, RecordBatchIterator, StructArray};
+use arrow_schema::ArrowError;
+use mock_data::output::Format;
+use mock_data::{Generator, PersonOptions};
 use pyo3::{
     create_exception,
     exceptions::{PyException, PyValueError},
     prelude::*,
+    types::{PyCapsule, PyDict, PyList},
 };
-use rand::{rngs::StdRng, seq::IteratorRandom, SeedableRng};
-use serde::{Deserialize, Serialize};
-use unicode_normalization::UnicodeNormalization;
 
-create_exception!(_pyo3_mock_data, FileNotFoundError, PyException);
+create_exception!(
+    _pyo3_mock_data,
+    MockDataError,
+    PyException,
+    "Base class of the errors of pyo3_mock_data."
+);
+create_exception!(
+    _pyo3_mock_data,
+    CitiesFileError,
+    MockDataError,
+    "The cities file can't be opened or read."
+);
+create_exception!(
+    _pyo3_mock_data,
+    CitiesParseError,
+    MockDataError,
+    "The cities file isn't a valid cities file."
+);
+create_exception!(
+    _pyo3_mock_data,
+    OutputWriteError,
+    MockDataError,
+    "An output file can't be created or written."
+);
 
-#[derive(Debug)]
-struct Location {
-    city: String,
-    state: String,
-    country: String,
-}
+/// Number of profiles generated in parallel and written at a time
+const CHUNK_SIZE: u32 = 100_000;
+/// Number of profiles generated in parallel at a time by an iterator that yields single persons
+const ITERATOR_BUFFER_SIZE: u32 = 1_000;
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// A generated person profile, with the fields of the CSV output as typed attributes
+#[pyclass(module = "pyo3_mock_data._pyo3_mock_data", frozen, get_all, eq)]
+#[derive(Debug, Clone, PartialEq)]
 struct Person {
     id: u32,
     name: String,
@@ -33,135 +55,309 @@ struct Person {
     city: String,
     state: String,
     country: String,
+    lat: f64,
+    lng: f64,
 }
 
-fn check_valid_file(filename: &PathBuf) -> PyResult<()> {
-    if !filename.is_file() {
-        return Err(FileNotFoundError::new_err(format!(
-            "File not found: {filename:?}"
-        )));
+impl From<mock_data::Person> for Person {
+    fn from(person: mock_data::Person) -> Self {
+        Person {
+            id: person.id,
+            name: person.name,
+            age: person.age,
+            is_married: person.is_married,
+            city: person.city,
+            state: person.state,
+            country: person.country,
+            lat: person.lat,
+            lng: person.lng,
+        }
     }
-
-    Ok(())
 }
 
-fn construct_person(locations: &[Location], id: u32) -> PyResult<Person> {
-    let mut r = StdRng::seed_from_u64(id as u64);
-    match locations.iter().choose(&mut r) {
-        Some(loc) => {
-            let name: String = Name(EN).fake_with_rng(&mut r);
-            let age: u8 = r.gen_range(18..65);
-            let is_married = r.gen_bool(0.5);
-
-            Ok(Person {
-                id,
-                name,
-                age,
-                is_married,
-                city: loc.city.to_string(),
-                state: loc.state.to_string(),
-                country: loc.country.to_string(),
-            })
-        }
-        None => Err(PyValueError::new_err("No location found")),
+#[pymethods]
+impl Person {
+    fn __repr__(&self) -> String {
+        format!(
+            "Person(id={}, name={:?}, age={}, is_married={}, city={:?}, state={:?}, country={:?}, \
+             lat={}, lng={})",
+            self.id,
+            self.name,
+            self.age,
+            if self.is_married { "True" } else { "False" },
+            self.city,
+            self.state,
+            self.country,
+            self.lat,
+            self.lng
+        )
+    }
+
+    /// The fields of the person as a dict, e.g. to build a DataFrame
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("id", self.id)?;
+        dict.set_item("name", &self.name)?;
+        dict.set_item("age", self.age)?;
+        dict.set_item("is_married", self.is_married)?;
+        dict.set_item("city", &self.city)?;
+        dict.set_item("state", &self.state)?;
+        dict.set_item("country", &self.country)?;
+        dict.set_item("lat", self.lat)?;
+        dict.set_item("lng", self.lng)?;
+        Ok(dict)
     }
 }
 
-fn convert_unicode_to_ascii(s: &str) -> String {
-    // First ensure that the unicode string is normalized to NFKD form
-    s.nfkd().filter(|c| c.is_ascii()).collect::<String>()
+/// Generated profiles as an Arrow record batch, which Arrow libraries such as pyarrow and polars
+/// can import without copying through the Arrow PyCapsule interface
+#[pyclass(module = "pyo3_mock_data._pyo3_mock_data", frozen)]
+struct PersonBatch {
+    batch: RecordBatch,
 }
 
-fn read_cities(path: &Path) -> PyResult<Vec<Location>> {
-    let rdr = csv::Reader::from_path(path);
-    match rdr {
-        Ok(mut r) => {
-            let mut cities: Vec<Location> = Vec::new();
-            for record in r.records() {
-                match record {
-                    Ok(re) => {
-                        let city = &re[1];
-                        let state = convert_unicode_to_ascii(&re[7]);
-                        let country = &re[4];
-                        cities.push(Location {
-                            city: city.to_string(),
-                            state: state.to_string(),
-                            country: country.to_string(),
-                        });
-                    }
-                    Err(_) => {
-                        return Err(PyValueError::new_err(
-                            "Did not obtain valid record from CSV file.",
-                        ))
-                    }
-                }
-            }
+fn arrow_err(err: ArrowError) -> PyErr {
+    MockDataError::new_err(format!("Unable to export the batch to Arrow: {err}"))
+}
+
+#[pymethods]
+impl PersonBatch {
+    #[getter]
+    fn num_rows(&self) -> usize {
+        self.batch.num_rows()
+    }
+
+    #[getter]
+    fn column_names(&self) -> Vec<String> {
+        let schema = self.batch.schema();
+        schema.fields().iter().map(|f| f.name().clone()).collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.batch.num_rows()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PersonBatch(num_rows={}, columns={:?})",
+            self.batch.num_rows(),
+            self.column_names()
+        )
+    }
+
+    /// Export the schema of the batch
+    fn __arrow_c_schema__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyCapsule>> {
+        let schema = FFI_ArrowSchema::try_from(self.batch.schema().as_ref()).map_err(arrow_err)?;
+        PyCapsule::new(py, schema, Some(c"arrow_schema".to_owned()))
+    }
 
-            Ok(cities)
+    /// Export the batch as a struct array. The requested schema is ignored, as the consumer may
+    /// cast the batch itself.
+    #[pyo3(signature = (requested_schema=None))]
+    fn __arrow_c_array__<'py>(
+        &self,
+        py: Python<'py>,
+        requested_schema: Option<Bound<'py, PyCapsule>>,
+    ) -> PyResult<(Bound<'py, PyCapsule>, Bound<'py, PyCapsule>)> {
+        let _ = requested_schema;
+        let array = StructArray::from(self.batch.clone());
+        let (array, schema): (FFI_ArrowArray, FFI_ArrowSchema) =
+            to_ffi(&array.to_data()).map_err(arrow_err)?;
+        Ok((
+            PyCapsule::new(py, schema, Some(c"arrow_schema".to_owned()))?,
+            PyCapsule::new(py, array, Some(c"arrow_array".to_owned()))?,
+        ))
+    }
+
+    /// Export the batch as a stream of a single batch
+    #[pyo3(signature = (requested_schema=None))]
+    fn __arrow_c_stream__<'py>(
+        &self,
+        py: Python<'py>,
+        requested_schema: Option<Bound<'py, PyCapsule>>,
+    ) -> PyResult<Bound<'py, PyCapsule>> {
+        let _ = requested_schema;
+        let reader = RecordBatchIterator::new([Ok(self.batch.clone())], self.batch.schema());
+        let stream = FFI_ArrowArrayStream::new(Box::new(reader));
+        PyCapsule::new(py, stream, Some(c"arrow_array_stream".to_owned()))
+    }
+}
+
+/// Yields persons lazily, one at a time or in lists of `batch_size`, up to `limit` persons (or as
+/// many as there are ids if `limit` is `None`). The cities are read once and kept in Rust memory,
+/// and the persons are generated in parallel in batches with the GIL released.
+#[pyclass(module = "pyo3_mock_data._pyo3_mock_data")]
+struct MockPersonIterator {
+    generator: Generator,
+    // Id of the next person to generate, which is past the last id once all are generated
+    next_id: u64,
+    last_id: u32,
+    batch_size: Option<u32>,
+    // Persons generated but not yet yielded
+    buffer: VecDeque<mock_data::Person>,
+}
+
+impl MockPersonIterator {
+    /// Generate up to `count` more persons, or none once the last id is reached
+    fn generate(&mut self, py: Python<'_>, count: u32) -> Vec<mock_data::Person> {
+        let last_id = u64::from(self.last_id).min(self.next_id + u64::from(count) - 1);
+        if self.next_id > last_id {
+            return Vec::new();
         }
-        Err(_) => Err(PyValueError::new_err(
-            "Cannot read cities CSV file. Please check the path.",
-        )),
+        let ids = self.next_id as u32..=last_id as u32;
+        self.next_id = last_id + 1;
+        let generator = &self.generator;
+        py.allow_threads(|| generator.persons_with_ids(ids))
     }
 }
 
-fn run(locations: &[Location], limit: u32, output_filename: &Option<PathBuf>) -> PyResult<()> {
-    let mut profiles: Vec<Person> = Vec::new();
-    for id in 1..=limit {
-        let person = construct_person(locations, id)?;
-        profiles.push(person);
-    }
-    let mut wtr = csv::Writer::from_writer(vec![]);
-    // Serialize the data to CSV and write it to file
-    for person in profiles.iter() {
-        if wtr.serialize(person).is_err() {
-            return Err(PyValueError::new_err("Unable to serialize output CSV"));
+#[pymethods]
+impl MockPersonIterator {
+    #[new]
+    #[pyo3(signature = (filename, limit=None, *, batch_size=None))]
+    fn new(
+        py: Python<'_>,
+        filename: PathBuf,
+        limit: Option<u32>,
+        batch_size: Option<u32>,
+    ) -> PyResult<Self> {
+        if batch_size == Some(0) {
+            return Err(PyValueError::new_err("batch_size must be positive"));
         }
+        let generator = py
+            .allow_threads(|| Generator::from_cities_file(&filename, PersonOptions::default(), 0))
+            .map_err(|err| to_py_err(py, err))?;
+        Ok(MockPersonIterator {
+            generator,
+            next_id: 1,
+            last_id: limit.unwrap_or(u32::MAX),
+            batch_size,
+            buffer: VecDeque::new(),
+        })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
     }
-    let result = wtr.into_inner();
-    match result {
-        Ok(r) => {
-            let output_path = match output_filename {
-                Some(p) => p,
-                None => Path::new("./data/persons.csv"),
-            };
-            write_csv(output_path, r)?;
-            Ok(())
+
+    /// The next person, or the next list of up to `batch_size` persons
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        if let Some(batch_size) = self.batch_size {
+            let persons = self.generate(py, batch_size);
+            if persons.is_empty() {
+                return Ok(None);
+            }
+            let persons = PyList::new(py, persons.into_iter().map(Person::from))?;
+            return Ok(Some(persons.into_any().unbind()));
+        }
+        if self.buffer.is_empty() {
+            let persons = self.generate(py, ITERATOR_BUFFER_SIZE);
+            self.buffer.extend(persons);
+        }
+        match self.buffer.pop_front() {
+            Some(person) => Ok(Some(
+                Person::from(person).into_pyobject(py)?.into_any().unbind(),
+            )),
+            None => Ok(None),
         }
-        Err(_) => Err(PyValueError::new_err("Unable to construct CSV output")),
     }
 }
 
-fn write_csv(output_path: &Path, result: Vec<u8>) -> PyResult<()> {
-    match fs::File::create(output_path) {
-        Ok(mut file) => {
-            if file.write_all(result.as_slice()).is_err() {
-                return Err(PyValueError::new_err("Unable to write to output CSV file"));
-            }
-            Ok(())
+/// The Python exception of an error of the generator. Its message includes the messages of the
+/// causes of the error (e.g. the I/O error), and the exception has the `path` of the file and, for
+/// parse errors, the `row` of the error as attributes.
+fn to_py_err(py: Python<'_>, err: mock_data::Error) -> PyErr {
+    let mut message = err.to_string();
+    let mut source = std::error::Error::source(&err);
+    while let Some(cause) = source {
+        message.push_str(&format!(": {cause}"));
+        source = cause.source();
+    }
+    let (py_err, path) = match &err {
+        mock_data::Error::CitiesFile { path, .. } => (CitiesFileError::new_err(message), path),
+        mock_data::Error::CitiesParse { path, .. } => (CitiesParseError::new_err(message), path),
+        mock_data::Error::OutputWrite { path, .. } => (OutputWriteError::new_err(message), path),
+        _ => return MockDataError::new_err(message),
+    };
+    let set_attributes = || -> PyResult<()> {
+        let value = py_err.value(py);
+        value.setattr("path", path)?;
+        if let mock_data::Error::CitiesParse { row, .. } = &err {
+            value.setattr("row", row)?;
         }
-        Err(_) => Err(PyValueError::new_err("Unable to create file for writer")),
+        Ok(())
+    };
+    match set_attributes() {
+        Ok(()) => py_err,
+        Err(err) => err,
     }
 }
 
-#[pyfunction(signature = (filename, limit=10, *, output_filename=None))]
+/// Generate `limit` person profiles and write them to a CSV file. With `as_list`, the profiles
+/// are returned as a list of `Person` instead, and only written if `output_filename` is given.
+///
+/// The GIL is released while the cities are read and the profiles are generated (in parallel), so
+/// that other Python threads can run in the meantime.
+#[pyfunction(signature = (filename, limit=10, *, output_filename=None, as_list=false))]
 fn generate_mock_persons(
+    py: Python<'_>,
     filename: PathBuf,
     limit: u32,
     output_filename: Option<PathBuf>,
-) -> PyResult<()> {
-    check_valid_file(&filename)?;
-    let locations = read_cities(&filename)?;
+    as_list: bool,
+) -> PyResult<Option<Vec<Person>>> {
     println!("Generating {limit:?} person profiles.");
-    run(&locations, limit, &output_filename)?;
+    let persons = py
+        .allow_threads(|| {
+            let generator = Generator::from_cities_file(&filename, PersonOptions::default(), 0)?;
+            if !as_list {
+                let output_path = match &output_filename {
+                    Some(p) => p,
+                    None => Path::new("./data/persons.csv"),
+                };
+                generator.write(output_path, Format::Csv, limit, CHUNK_SIZE)?;
+                return Ok(None);
+            }
+            let persons = generator.persons(limit);
+            if let Some(output_path) = &output_filename {
+                generator.write_persons(output_path, Format::Csv, &persons, CHUNK_SIZE)?;
+            }
+            Ok(Some(persons))
+        })
+        .map_err(|err| to_py_err(py, err))?;
 
-    Ok(())
+    Ok(persons.map(|persons| persons.into_iter().map(Person::from).collect()))
+}
+
+/// Generate `limit` person profiles as an Arrow record batch, with the same columns as the CSV file.
+/// Like `generate_mock_persons`, the GIL is released during generation.
+#[pyfunction(signature = (filename, limit=10))]
+fn generate_mock_persons_arrow(
+    py: Python<'_>,
+    filename: PathBuf,
+    limit: u32,
+) -> PyResult<PersonBatch> {
+    let batch = py
+        .allow_threads(|| {
+            let generator = Generator::from_cities_file(&filename, PersonOptions::default(), 0)?;
+            generator.record_batch(limit)
+        })
+        .map_err(|err| to_py_err(py, err))?;
+
+    Ok(PersonBatch { batch })
 }
 
 #[pymodule]
 fn _pyo3_mock_data(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(generate_mock_persons, m)?)?;
-    m.add("FileNotFoundError", py.get_type::<FileNotFoundError>())?;
+    m.add_function(wrap_pyfunction!(generate_mock_persons_arrow, m)?)?;
+    m.add_class::<Person>()?;
+    m.add_class::<PersonBatch>()?;
+    m.add_class::<MockPersonIterator>()?;
+    m.add("MockDataError", py.get_type::<MockDataError>())?;
+    m.add("CitiesFileError", py.get_type::<CitiesFileError>())?;
+    m.add("CitiesParseError", py.get_type::<CitiesParseError>())?;
+    m.add("OutputWriteError", py.get_type::<OutputWriteError>())?;
 
     Ok(())
 }
@@ -1,6 +1,17 @@
+from concurrent.futures import ThreadPoolExecutor
+
 import pytest
 
-from pyo3_mock_data import generate_mock_persons
+from pyo3_mock_data import (
+    CitiesFileError,
+    CitiesParseError,
+    MockDataError,
+    MockPersonIterator,
+    OutputWriteError,
+    Person,
+    generate_mock_persons,
+    generate_mock_persons_arrow,
+)
 
 
 @pytest.mark.parametrize("limit", (10, 20))
@@ -20,3 +31,81 @@ def test_write_persons_to_csv_default_limit(tmp_path):
     with open(output_path) as f:
         lines = len(f.readlines()) - 1  # Remove the trailing new line
     assert lines == 10
+
+
+def test_generate_persons_as_list(tmp_path):
+    persons = generate_mock_persons("./data/worldcities.csv", limit=5, as_list=True)
+    assert [person.id for person in persons] == [1, 2, 3, 4, 5]
+    assert all(isinstance(person, Person) for person in persons)
+    assert all(18 <= person.age <= 64 for person in persons)
+    assert persons[0].to_dict()["name"] == persons[0].name
+
+    # The same persons are written to the output file if one is given
+    output_path = tmp_path / "persons.csv"
+    again = generate_mock_persons(
+        "./data/worldcities.csv", limit=5, output_filename=output_path, as_list=True
+    )
+    assert again == persons
+    with open(output_path) as f:
+        assert len(f.readlines()) - 1 == 5
+
+
+def test_generate_persons_arrow():
+    batch = generate_mock_persons_arrow("./data/worldcities.csv", limit=5)
+    assert len(batch) == batch.num_rows == 5
+    assert batch.column_names[:4] == ["id", "name", "age", "isMarried"]
+
+    pa = pytest.importorskip("pyarrow")
+    record_batch = pa.record_batch(batch)
+    persons = generate_mock_persons("./data/worldcities.csv", limit=5, as_list=True)
+    assert record_batch.column("name").to_pylist() == [person.name for person in persons]
+    assert pa.table(batch).num_rows == 5
+
+
+def test_generate_persons_in_threads():
+    # Generation releases the GIL, so several calls can run in background threads at once
+    def generate(limit):
+        return generate_mock_persons("./data/worldcities.csv", limit=limit, as_list=True)
+
+    with ThreadPoolExecutor(max_workers=4) as executor:
+        results = list(executor.map(generate, [100, 200, 300, 400]))
+    assert [len(persons) for persons in results] == [100, 200, 300, 400]
+    # Each person only depends on its id, whichever thread generated it
+    assert all(persons[:100] == results[0] for persons in results)
+
+
+def test_iterate_persons():
+    persons = generate_mock_persons("./data/worldcities.csv", limit=25, as_list=True)
+    assert list(MockPersonIterator("./data/worldcities.csv", 25)) == persons
+
+    batches = list(MockPersonIterator("./data/worldcities.csv", 25, batch_size=10))
+    assert [len(batch) for batch in batches] == [10, 10, 5]
+    assert [person for batch in batches for person in batch] == persons
+
+    # Without a limit, persons are generated for as long as they are consumed
+    iterator = MockPersonIterator("./data/worldcities.csv")
+    assert [next(iterator).id for _ in range(3)] == [1, 2, 3]
+
+
+def test_errors(tmp_path):
+    with pytest.raises(CitiesFileError, match="No such file or directory") as exc_info:
+        generate_mock_persons(tmp_path / "missing.csv")
+    assert exc_info.value.path == str(tmp_path / "missing.csv")
+    assert isinstance(exc_info.value, MockDataError)
+
+    cities_path = tmp_path / "cities.csv"
+    cities_path.write_text("name,lat,lng\nTokyo,35.6897,139.6922\n")
+    message = 'Column "city_ascii" of the city not found'
+    with pytest.raises(CitiesParseError, match=message) as exc_info:
+        generate_mock_persons_arrow(cities_path)
+    assert (exc_info.value.path, exc_info.value.row) == (str(cities_path), None)
+
+    cities_path.write_bytes(b"city\xff,lat,lng\nTokyo,35.6897,139.6922\n")
+    with pytest.raises(CitiesParseError, match="invalid utf-8") as exc_info:
+        MockPersonIterator(cities_path)
+    assert exc_info.value.row == 1
+
+    output_path = tmp_path / "missing" / "persons.csv"
+    with pytest.raises(OutputWriteError) as exc_info:
+        generate_mock_persons("./data/worldcities.csv", output_filename=output_path)
+    assert exc_info.value.path == str(output_path)